@@ -0,0 +1,49 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+
+const MAX_ACTIVITY_ENTRIES: usize = 500;
+
+pub const LEVEL_ERROR: &str = "error";
+pub const LEVEL_WARN: &str = "warn";
+
+/// A significant event (upload failure, retry, heartbeat failure) for the in-app activity feed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    /// Epoch millis
+    pub timestamp: u64,
+    pub level: String,
+    pub message: String,
+}
+
+/// The most recent activity entries, oldest first.
+pub type ActivityLogState = Arc<Mutex<VecDeque<ActivityEntry>>>;
+
+/// Add an entry to the activity feed and emit it as `log_event`. This is in addition to the
+/// regular log, which callers still write to.
+pub fn record_activity(app_handle: &AppHandle, level: &str, message: String) {
+    let entry = ActivityEntry {
+        timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        level: level.to_string(),
+        message,
+    };
+    if let Some(activity_log) = app_handle.try_state::<ActivityLogState>() {
+        let mut activity_log = activity_log.lock();
+        if activity_log.len() >= MAX_ACTIVITY_ENTRIES {
+            activity_log.pop_front();
+        }
+        activity_log.push_back(entry.clone());
+    }
+    let _ = app_handle.emit("log_event", &entry);
+}
+
+#[tauri::command]
+pub fn get_recent_events(
+    activity_log: tauri::State<'_, ActivityLogState>,
+) -> Result<Vec<ActivityEntry>, AppError> {
+    Ok(activity_log.lock().iter().cloned().collect())
+}
@@ -0,0 +1,84 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+const AUDIT_LOG_FILENAME: &str = "sync-audit.jsonl";
+const MAX_AUDIT_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated files kept as `sync-audit.jsonl.1` (newest) up to `.2`.
+const RETAINED_AUDIT_LOGS: usize = 2;
+
+pub const AUDIT_EVENT_UPLOADED: &str = "upload_succeeded";
+pub const AUDIT_EVENT_RETRYING: &str = "upload_retrying";
+pub const AUDIT_EVENT_FAILED: &str = "upload_failed";
+
+/// Serializes appends, so concurrent uploads don't interleave lines or rotate twice.
+static AUDIT_LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// One line of the audit log.
+#[derive(Serialize)]
+pub struct AuditRecord<'a> {
+    /// Epoch millis
+    pub timestamp: u64,
+    pub event: &'static str,
+    pub relative_path: &'a str,
+    /// File status after the event, as in `file_upload_status`
+    pub status: &'static str,
+    pub error: Option<&'a str>,
+    pub bytes: u64,
+    pub duration_ms: u64,
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{index}"));
+    PathBuf::from(rotated)
+}
+
+/// Move the full log aside, shifting older files along and dropping the oldest.
+fn rotate(path: &Path) -> std::io::Result<()> {
+    for index in (1..RETAINED_AUDIT_LOGS).rev() {
+        let from = rotated_path(path, index);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, index + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+fn append(path: &Path, line: &str) -> std::io::Result<()> {
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= MAX_AUDIT_LOG_BYTES) {
+        rotate(path)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Append a record to the audit log in the app data dir. Failures are only logged, so
+/// auditing never holds up an upload.
+pub fn write_audit_record(app_handle: &AppHandle, record: &AuditRecord) {
+    let path = match app_handle.path().app_data_dir() {
+        Ok(dir) => dir.join(AUDIT_LOG_FILENAME),
+        Err(e) => {
+            log::warn!("Can't write audit log, no app data dir: {e}");
+            return;
+        }
+    };
+    let line = match serde_json::to_string(record) {
+        Ok(line) => line,
+        Err(e) => {
+            log::warn!("Failed to serialize audit record: {e}");
+            return;
+        }
+    };
+
+    let _guard = AUDIT_LOG_LOCK.lock();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(e) = append(&path, &line) {
+        log::warn!("Failed to write audit log {}: {e}", path.display());
+    }
+}
@@ -0,0 +1,160 @@
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::activity::{record_activity, LEVEL_ERROR};
+use crate::http_client::{check_response, SharedHttpClient};
+
+const SETTINGS_STORE_FILENAME: &str = "settings.json";
+const TOKEN_STORE_KEY: &str = "token";
+const REFRESH_TOKEN_STORE_KEY: &str = "refresh_token";
+const TOKEN_REFRESH_URL_STORE_KEY: &str = "token_refresh_url";
+const TOKEN_REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Only one refresh runs at a time, so a burst of 401s doesn't spend a single-use refresh
+/// token several times.
+static REFRESH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Serialize)]
+struct RefreshTokenBody {
+    #[serde(rename = "refreshToken")]
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshTokenResponse {
+    token: String,
+    /// Set when the server rotates the refresh token as well
+    refresh_token: Option<String>,
+}
+
+/// Emitted when the auth token was rejected and couldn't be refreshed, so the user has to
+/// sign in again.
+#[derive(Clone, Serialize)]
+struct AuthErrorEvent {
+    reason: String,
+}
+
+fn store_string(app_handle: &AppHandle, key: &str) -> Result<Option<String>, String> {
+    let store = app_handle
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to access store: {e}"))?;
+    Ok(store.get(key).and_then(|v| v.as_str().map(String::from)))
+}
+
+async fn request_new_token(
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<String, String> {
+    let refresh_url = store_string(app_handle, TOKEN_REFRESH_URL_STORE_KEY)?
+        .ok_or_else(|| "No token refresh URL configured".to_string())?;
+    let refresh_token = store_string(app_handle, REFRESH_TOKEN_STORE_KEY)?
+        .ok_or_else(|| "No refresh token stored".to_string())?;
+
+    let response = client
+        .post(&refresh_url)
+        .timeout(TOKEN_REFRESH_TIMEOUT)
+        .json(&RefreshTokenBody { refresh_token })
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send token refresh request: {e}"))?;
+    let response = check_response(response, "Token refresh").await?;
+    let refreshed: RefreshTokenResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse token refresh response: {e}"))?;
+
+    let store = app_handle
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to access store: {e}"))?;
+    store.set(
+        TOKEN_STORE_KEY,
+        serde_json::Value::String(refreshed.token.clone()),
+    );
+    if let Some(refresh_token) = refreshed.refresh_token {
+        store.set(
+            REFRESH_TOKEN_STORE_KEY,
+            serde_json::Value::String(refresh_token),
+        );
+    }
+    Ok(refreshed.token)
+}
+
+/// Get a new token to replace `rejected_token`. If another request already refreshed it
+/// meanwhile, that token is returned instead. On failure an `auth_error` event is emitted.
+async fn refresh_auth_token(
+    rejected_token: Option<&str>,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<String, String> {
+    let _guard = REFRESH_LOCK.lock().await;
+
+    if let Ok(Some(current)) = store_string(app_handle, TOKEN_STORE_KEY) {
+        if Some(current.as_str()) != rejected_token {
+            return Ok(current);
+        }
+    }
+
+    match request_new_token(client, app_handle).await {
+        Ok(token) => {
+            log::info!("Refreshed auth token");
+            Ok(token)
+        }
+        Err(e) => {
+            let message = format!("Auth token was rejected and couldn't be refreshed: {e}");
+            log::error!("{message}");
+            record_activity(app_handle, LEVEL_ERROR, message.clone());
+            let _ = app_handle.emit("auth_error", AuthErrorEvent { reason: e });
+            Err(message)
+        }
+    }
+}
+
+/// Why `send_with_token_refresh` failed.
+#[derive(Debug)]
+pub enum SendError {
+    /// The request couldn't be sent, e.g. a connection, DNS or timeout failure
+    Request(String),
+    /// The server rejected the token and it couldn't be refreshed
+    Auth(String),
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Request(message) | SendError::Auth(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Send a request built with `token`. If the server rejects the token with 401, refresh it
+/// and send the request once more with the new one. Returns the response along with the
+/// refreshed token, if there was one.
+pub async fn send_with_token_refresh<F>(
+    token: Option<String>,
+    build_request: F,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(Response, Option<String>), SendError>
+where
+    F: Fn(Option<&str>) -> RequestBuilder,
+{
+    let response = build_request(token.as_deref())
+        .send()
+        .await
+        .map_err(|e| SendError::Request(e.to_string()))?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok((response, None));
+    }
+
+    let new_token = refresh_auth_token(token.as_deref(), client, app_handle)
+        .await
+        .map_err(SendError::Auth)?;
+    let response = build_request(Some(&new_token))
+        .send()
+        .await
+        .map_err(|e| SendError::Request(e.to_string()))?;
+    Ok((response, Some(new_token)))
+}
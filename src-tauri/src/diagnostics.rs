@@ -4,6 +4,8 @@ use std::error::Error as StdError;
 use std::time::{Duration, Instant};
 use tauri::AppHandle;
 
+use crate::error::AppError;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DiagnosticCheck {
     pub name: String,
@@ -228,7 +230,7 @@ fn collect_proxy_env() -> Vec<(String, String)> {
 pub async fn run_network_diagnostics(
     server_url: String,
     app_handle: AppHandle,
-) -> Result<NetworkDiagnostics, String> {
+) -> Result<NetworkDiagnostics, AppError> {
     let parsed = Url::parse(&server_url)
         .map_err(|e| format!("Invalid server URL {server_url:?}: {e}"))?;
     let host = parsed
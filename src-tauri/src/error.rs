@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Error returned by commands. Serialized with a `kind` tag the frontend can switch on.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    FolderNotFound {
+        path: String,
+    },
+    NotADirectory {
+        path: String,
+    },
+    FolderNotWatched {
+        path: String,
+    },
+    TooManyFolders {
+        max: usize,
+    },
+    /// The server couldn't be reached, or answered with an error `status`
+    Network {
+        status: Option<u16>,
+        message: String,
+    },
+    NotAuthenticated,
+    /// The upload configuration was rejected; `fields` names the invalid settings
+    Config {
+        fields: Vec<String>,
+        message: String,
+    },
+    NoActiveHeartbeat,
+    UploadsDisabled,
+    NotFound {
+        message: String,
+    },
+    Other {
+        message: String,
+    },
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::FolderNotFound { path } => write!(f, "Folder does not exist: {path}"),
+            AppError::NotADirectory { path } => write!(f, "Path is not a directory: {path}"),
+            AppError::FolderNotWatched { path } => write!(f, "Folder is not being watched: {path}"),
+            AppError::TooManyFolders { max } => {
+                write!(f, "Already watching the maximum of {max} folders")
+            }
+            AppError::Network { message, .. }
+            | AppError::Config { message, .. }
+            | AppError::NotFound { message }
+            | AppError::Other { message } => f.write_str(message),
+            AppError::NotAuthenticated => f.write_str("Not authenticated"),
+            AppError::NoActiveHeartbeat => f.write_str("No active heartbeat"),
+            AppError::UploadsDisabled => f.write_str("Uploads are disabled"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other { message }
+    }
+}
@@ -0,0 +1,117 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parsed `.gitignore` and `.syncignore` rules by file path, so events don't re-read them
+/// every time.
+pub type GitignoreCacheState = Arc<Mutex<HashMap<PathBuf, CachedGitignore>>>;
+
+pub struct CachedGitignore {
+    /// Modification time of the ignore file when parsed, or None if there wasn't one.
+    /// A mismatch means the file was added, edited or removed and must be re-read.
+    modified: Option<SystemTime>,
+    matcher: Option<Arc<Gitignore>>,
+}
+
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+const SYNCIGNORE_FILE_NAME: &str = ".syncignore";
+
+fn load_matcher(dir: &Path, ignore_path: &Path) -> Option<Arc<Gitignore>> {
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(ignore_path) {
+        warn!("Problem reading {ignore_path:?}: {e}");
+    }
+    match builder.build() {
+        Ok(matcher) => Some(Arc::new(matcher)),
+        Err(e) => {
+            warn!("Failed to parse {ignore_path:?}: {e}");
+            None
+        }
+    }
+}
+
+fn matcher_for_dir(
+    dir: &Path,
+    file_name: &str,
+    cache: &GitignoreCacheState,
+) -> Option<Arc<Gitignore>> {
+    let ignore_path = dir.join(file_name);
+    let modified = fs::metadata(&ignore_path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.modified().unwrap_or(UNIX_EPOCH));
+
+    if let Some(cached) = cache.lock().get(&ignore_path) {
+        if cached.modified == modified {
+            return cached.matcher.clone();
+        }
+    }
+
+    let matcher = modified.and(load_matcher(dir, &ignore_path));
+    cache.lock().insert(
+        ignore_path,
+        CachedGitignore {
+            modified,
+            matcher: matcher.clone(),
+        },
+    );
+    matcher
+}
+
+/// Whether `path` is excluded by a `.gitignore` in `base_path` or any directory between it
+/// and the path. As with git, rules in deeper `.gitignore` files take precedence.
+pub fn is_gitignored(
+    path: &Path,
+    base_path: &Path,
+    is_dir: bool,
+    cache: &GitignoreCacheState,
+) -> bool {
+    let Ok(relative) = path.strip_prefix(base_path) else {
+        return false;
+    };
+
+    let mut dirs = vec![base_path.to_path_buf()];
+    if let Some(parent) = relative.parent() {
+        let mut dir = base_path.to_path_buf();
+        for component in parent.components() {
+            dir.push(component);
+            dirs.push(dir.clone());
+        }
+    }
+
+    for dir in dirs.iter().rev() {
+        let Some(matcher) = matcher_for_dir(dir, GITIGNORE_FILE_NAME, cache) else {
+            continue;
+        };
+        match matcher.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+    }
+    false
+}
+
+/// Whether `path` is excluded by the `.syncignore` in `base_path`. Unlike `.gitignore`, only
+/// the one in the watched folder itself is read. It's re-read whenever it changes.
+pub fn is_syncignored(
+    path: &Path,
+    base_path: &Path,
+    is_dir: bool,
+    cache: &GitignoreCacheState,
+) -> bool {
+    if path.strip_prefix(base_path).is_err() {
+        return false;
+    }
+    matcher_for_dir(base_path, SYNCIGNORE_FILE_NAME, cache).is_some_and(|matcher| {
+        matcher
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    })
+}
@@ -1,23 +1,57 @@
+use rand::Rng;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_store::StoreExt;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use tokio::time::{interval, MissedTickBehavior};
+use tokio::time::sleep;
 
+use crate::activity::{record_activity, LEVEL_ERROR};
+use crate::auth::{send_with_token_refresh, SendError};
+use crate::error::AppError;
 use crate::http_client::{check_response, SharedHttpClient};
+use crate::upload::{UploadProgressState, UploadQueue};
 
 const SETTINGS_STORE_FILENAME: &str = "settings.json";
 
 const HEARTBEAT_INTERVAL_SECS: u64 = 30;
+const MAX_HEARTBEAT_BACKOFF: Duration = Duration::from_secs(300);
+/// Well under the interval, so a hung request can't delay the next heartbeat.
+const HEARTBEAT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Each wait is randomly stretched or shrunk by up to this fraction so a fleet of devices
+/// started together doesn't stay in lockstep. Symmetric, so the average cadence is unchanged.
+const HEARTBEAT_JITTER_FRACTION: f64 = 0.1;
+/// The server couldn't be reached at all
 const OFFLINE_STATUS: &str = "offline";
+/// The server answered with an error status, or rejected a token that couldn't be refreshed
+const DEGRADED_STATUS: &str = "degraded";
+/// The server answered, but with a response that couldn't be parsed
+const ERROR_STATUS: &str = "error";
+
+/// A failed heartbeat, with which of the failure statuses it maps to.
+struct HeartbeatError {
+    kind: &'static str,
+    message: String,
+}
 
+/// Heartbeat body: `{device_fingerprint, app_version, queued?, uploaded?, failed?}`. The
+/// counts are left out when the upload state isn't available.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct HeartbeatRequest {
     device_fingerprint: String,
     app_version: String,
+    /// Files waiting in the upload queue
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queued: Option<usize>,
+    /// Files uploaded since the counters were last reset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uploaded: Option<usize>,
+    /// Files that permanently failed since the counters were last reset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failed: Option<usize>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -35,20 +69,49 @@ pub struct HeartbeatStatus {
     pub status: Option<HeartbeatResponse>,
     pub is_loading: bool,
     pub error: Option<String>,
+    /// Why the last attempt failed: `offline`, `degraded` or `error`. None after a success.
+    pub failure_kind: Option<String>,
+    /// Failed attempts since the last success; the next attempt is delayed accordingly.
+    pub consecutive_failures: u32,
+    /// Heartbeats are suspended by `pause_heartbeat`; the config is kept for resuming.
+    pub paused: bool,
+    /// Successful and failed heartbeats since launch, kept across restarts of the heartbeat.
+    pub successes_total: u64,
+    pub failures_total: u64,
+    /// Epoch millis of the last successful heartbeat
+    pub last_success_at: Option<u64>,
 }
 
 #[derive(Clone)]
 pub struct HeartbeatConfig {
     pub url: String,
+    /// Path `url` was built from, so it can be rebuilt when the server URL changes
+    pub path: String,
     pub token: String,
     pub device_fingerprint: String,
     pub app_version: String,
+    pub extra_headers: HeaderMap,
+    /// Wait before the first heartbeat after each (re)start; 0 sends it immediately.
+    pub initial_delay_secs: u64,
 }
 
 pub type HeartbeatState = Arc<Mutex<Option<HeartbeatConfig>>>;
 pub type HeartbeatStatusState = Arc<Mutex<HeartbeatStatus>>;
 pub type HeartbeatTaskState = Arc<Mutex<Option<JoinHandle<()>>>>;
 
+/// Normal interval after a success, doubling per consecutive failure up to a cap.
+fn heartbeat_delay(consecutive_failures: u32) -> Duration {
+    Duration::from_secs(HEARTBEAT_INTERVAL_SECS)
+        .checked_mul(2u32.saturating_pow(consecutive_failures))
+        .unwrap_or(MAX_HEARTBEAT_BACKOFF)
+        .min(MAX_HEARTBEAT_BACKOFF)
+}
+
+fn with_jitter(delay: Duration) -> Duration {
+    let factor = rand::thread_rng().gen_range(-HEARTBEAT_JITTER_FRACTION..=HEARTBEAT_JITTER_FRACTION);
+    delay.mul_f64(1.0 + factor)
+}
+
 pub async fn start_heartbeat(
     config: HeartbeatConfig,
     http_client: SharedHttpClient,
@@ -74,8 +137,9 @@ pub async fn start_heartbeat(
     let app_handle_clone = app_handle.clone();
 
     let task_handle = tokio::spawn(async move {
-        let mut interval = interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
-        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        if config.initial_delay_secs > 0 {
+            sleep(Duration::from_secs(config.initial_delay_secs)).await;
+        }
 
         loop {
             let config = {
@@ -87,75 +151,170 @@ pub async fn start_heartbeat(
                 break;
             };
 
-            let result = make_heartbeat_request(&http_client, &config).await;
-            let status = match result {
-                Ok(response) => {
-                    log::info!("Heartbeat successful");
-
-                    // Handle token rotation
-                    if let Some(ref new_token) = response.new_token {
-                        log::info!("Received rotated token from server, updating store");
-                        match app_handle_clone.store(SETTINGS_STORE_FILENAME) {
-                            Ok(store) => {
-                                store.set(
-                                    "token",
-                                    serde_json::Value::String(new_token.clone()),
-                                );
-                                // Update the in-memory config so future heartbeats use the new token
-                                {
-                                    let mut state = heartbeat_state_clone.lock().await;
-                                    if let Some(ref mut cfg) = *state {
-                                        cfg.token = new_token.clone();
-                                    }
-                                }
+            if status_state_clone.lock().await.paused {
+                sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+                continue;
+            }
+
+            let status = send_heartbeat(
+                &config,
+                &http_client,
+                &heartbeat_state_clone,
+                &status_state_clone,
+                &app_handle_clone,
+            )
+            .await;
 
-                            }
-                            Err(e) => {
-                                log::error!("Failed to persist rotated token: {e}");
+            sleep(with_jitter(heartbeat_delay(status.consecutive_failures))).await;
+        }
+    });
+
+    {
+        let mut task = task_state.lock().await;
+        *task = Some(task_handle);
+    }
+
+    Ok(())
+}
+
+/// Send one heartbeat, then record and emit the resulting status.
+async fn send_heartbeat(
+    config: &HeartbeatConfig,
+    http_client: &SharedHttpClient,
+    heartbeat_state: &HeartbeatState,
+    status_state: &HeartbeatStatusState,
+    app_handle: &AppHandle,
+) -> HeartbeatStatus {
+    let previous = status_state.lock().await.clone();
+    let (previous_response, previous_failures) = (previous.status, previous.consecutive_failures);
+
+    let result = make_heartbeat_request(http_client, config, app_handle).await;
+    let status = match result {
+        Ok(response) => {
+            log::info!("Heartbeat successful");
+
+            // Handle token rotation
+            if let Some(ref new_token) = response.new_token {
+                log::info!("Received rotated token from server, updating store");
+                match app_handle.store(SETTINGS_STORE_FILENAME) {
+                    Ok(store) => {
+                        store.set("token", serde_json::Value::String(new_token.clone()));
+                        // Update the in-memory config so future heartbeats use the new token
+                        {
+                            let mut state = heartbeat_state.lock().await;
+                            if let Some(ref mut cfg) = *state {
+                                cfg.token = new_token.clone();
                             }
                         }
                     }
-
-                    HeartbeatStatus {
-                        status: Some(response),
-                        is_loading: false,
-                        error: None,
+                    Err(e) => {
+                        log::error!("Failed to persist rotated token: {e}");
                     }
                 }
-                Err(e) => {
-                    log::error!("Heartbeat failed: {e}");
-                    let previous_response = {
-                        let state = status_state_clone.lock().await;
-                        state.status.clone()
-                    };
-                    let failed_response = previous_response.map(|mut prev| {
-                        prev.status = OFFLINE_STATUS.to_string();
-                        prev
-                    });
-                    HeartbeatStatus {
-                        status: failed_response,
-                        is_loading: false,
-                        error: Some(e),
-                    }
-                }
-            };
-
-            {
-                let mut state = status_state_clone.lock().await;
-                *state = status.clone();
             }
-            let _ = app_handle_clone.emit("heartbeat_status", &status);
 
-            interval.tick().await;
+            HeartbeatStatus {
+                status: Some(response),
+                is_loading: false,
+                error: None,
+                failure_kind: None,
+                consecutive_failures: 0,
+                paused: false,
+                successes_total: previous.successes_total + 1,
+                failures_total: previous.failures_total,
+                last_success_at: Some(chrono::Utc::now().timestamp_millis() as u64),
+            }
         }
-    });
+        Err(HeartbeatError { kind, message: e }) => {
+            let consecutive_failures = previous_failures.saturating_add(1);
+            let message = format!("Heartbeat failed ({consecutive_failures} in a row): {e}");
+            log::error!("{message}");
+            record_activity(app_handle, LEVEL_ERROR, message);
+            let failed_response = previous_response.map(|mut prev| {
+                prev.status = kind.to_string();
+                prev
+            });
+            HeartbeatStatus {
+                status: failed_response,
+                is_loading: false,
+                error: Some(e),
+                failure_kind: Some(kind.to_string()),
+                consecutive_failures,
+                paused: false,
+                successes_total: previous.successes_total,
+                failures_total: previous.failures_total + 1,
+                last_success_at: previous.last_success_at,
+            }
+        }
+    };
 
-    {
-        let mut task = task_state.lock().await;
-        *task = Some(task_handle);
+    let status = {
+        let mut state = status_state.lock().await;
+        // Keep a pause made while the request was in flight
+        *state = HeartbeatStatus {
+            paused: state.paused,
+            ..status
+        };
+        state.clone()
+    };
+    let _ = app_handle.emit("heartbeat_status", &status);
+    status
+}
+
+/// Send a heartbeat right away with the running heartbeat's config, outside its schedule.
+pub async fn trigger_heartbeat(
+    http_client: SharedHttpClient,
+    heartbeat_state: HeartbeatState,
+    status_state: HeartbeatStatusState,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    let config = {
+        let state = heartbeat_state.lock().await;
+        state.clone()
+    };
+    let config = config.ok_or(AppError::NoActiveHeartbeat)?;
+    Ok(send_heartbeat(
+        &config,
+        &http_client,
+        &heartbeat_state,
+        &status_state,
+        &app_handle,
+    )
+    .await)
+}
+
+/// Suspend heartbeats without clearing the config, so resuming needs no arguments.
+pub async fn pause_heartbeat(
+    heartbeat_state: HeartbeatState,
+    status_state: HeartbeatStatusState,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    if heartbeat_state.lock().await.is_none() {
+        return Err(AppError::NoActiveHeartbeat);
     }
+    let status = {
+        let mut state = status_state.lock().await;
+        state.paused = true;
+        state.clone()
+    };
+    log::info!("Heartbeat paused");
+    let _ = app_handle.emit("heartbeat_status", &status);
+    Ok(status)
+}
 
-    Ok(())
+/// Resume paused heartbeats with the same config, sending one right away.
+pub async fn resume_heartbeat(
+    http_client: SharedHttpClient,
+    heartbeat_state: HeartbeatState,
+    status_state: HeartbeatStatusState,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    if heartbeat_state.lock().await.is_none() {
+        return Err(AppError::NoActiveHeartbeat);
+    }
+    status_state.lock().await.paused = false;
+    log::info!("Heartbeat resumed");
+    trigger_heartbeat(http_client, heartbeat_state, status_state, app_handle).await
 }
 
 pub async fn stop_heartbeat(
@@ -182,6 +341,12 @@ pub async fn stop_heartbeat(
             status: None,
             is_loading: false,
             error: None,
+            failure_kind: None,
+            consecutive_failures: 0,
+            paused: false,
+            successes_total: status.successes_total,
+            failures_total: status.failures_total,
+            last_success_at: status.last_success_at,
         };
     }
 
@@ -192,28 +357,74 @@ pub async fn stop_heartbeat(
 async fn make_heartbeat_request(
     client: &SharedHttpClient,
     config: &HeartbeatConfig,
-) -> Result<HeartbeatResponse, String> {
+    app_handle: &AppHandle,
+) -> Result<HeartbeatResponse, HeartbeatError> {
+    let (uploaded, failed) = match app_handle.try_state::<UploadProgressState>() {
+        Some(progress) => {
+            let progress = progress.lock();
+            (Some(progress.total_uploaded), Some(progress.total_failed))
+        }
+        None => (None, None),
+    };
     let request_body = HeartbeatRequest {
         device_fingerprint: config.device_fingerprint.clone(),
         app_version: config.app_version.clone(),
+        queued: app_handle
+            .try_state::<UploadQueue>()
+            .map(|queue| queue.lock().len()),
+        uploaded,
+        failed,
     };
 
     log::info!("Making heartbeat request to: {}", config.url);
 
-    let response = client
-        .post(&config.url)
-        .header("Authorization", format!("Bearer {}", config.token))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {e}"))?;
-
-    let response = check_response(response, "Heartbeat").await?;
-
-    response
-        .json()
+    let build_request = |token: Option<&str>| {
+        client
+            .post(&config.url)
+            .timeout(HEARTBEAT_REQUEST_TIMEOUT)
+            .headers(config.extra_headers.clone())
+            .header(
+                "Authorization",
+                format!("Bearer {}", token.unwrap_or_default()),
+            )
+            .json(&request_body)
+    };
+    let (response, refreshed_token) = send_with_token_refresh(
+        Some(config.token.clone()),
+        build_request,
+        client,
+        app_handle,
+    )
+    .await
+    .map_err(|e| match e {
+        SendError::Request(e) => HeartbeatError {
+            kind: OFFLINE_STATUS,
+            message: format!("Request failed: {e}"),
+        },
+        // The server answered, but with a 401 that refreshing the token didn't fix
+        SendError::Auth(message) => HeartbeatError {
+            kind: DEGRADED_STATUS,
+            message,
+        },
+    })?;
+
+    let response = check_response(response, "Heartbeat")
         .await
-        .map_err(|e| format!("Failed to parse response: {e}"))
+        .map_err(|message| HeartbeatError {
+            kind: DEGRADED_STATUS,
+            message,
+        })?;
+
+    let mut heartbeat_response: HeartbeatResponse =
+        response.json().await.map_err(|e| HeartbeatError {
+            kind: ERROR_STATUS,
+            message: format!("Failed to parse response: {e}"),
+        })?;
+    // A refreshed token is picked up the same way as one rotated by the server
+    if heartbeat_response.new_token.is_none() {
+        heartbeat_response.new_token = refreshed_token;
+    }
+    Ok(heartbeat_response)
 }
 
 pub async fn get_heartbeat_status(status_state: HeartbeatStatusState) -> HeartbeatStatus {
@@ -1,11 +1,42 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Response};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
 
 /// Shared HTTP client for connection pooling across all modules.
 pub type SharedHttpClient = Arc<Client>;
 
+/// Only connecting is bounded here; a whole-request timeout would cut off large uploads, so
+/// callers set their own per request.
 pub fn create_shared_client() -> SharedHttpClient {
-    Arc::new(Client::new())
+    let client = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .build()
+        .unwrap_or_else(|e| {
+            log::error!("Failed to build HTTP client, using defaults: {e}");
+            Client::new()
+        });
+    Arc::new(client)
+}
+
+/// Parse configured extra headers, failing on the first invalid name or value.
+pub fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("invalid header name '{name}': {e}"))?;
+        let header_value = HeaderValue::from_str(value)
+            .map_err(|e| format!("invalid value for header '{name}': {e}"))?;
+        header_map.insert(header_name, header_value);
+    }
+    Ok(header_map)
 }
 
 /// Check an HTTP response status and return a descriptive error if it failed.
@@ -1,13 +1,15 @@
+use notify::event::{ModifyKind, RenameMode};
+use ignore::{WalkBuilder, WalkState};
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use parking_lot::Mutex;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use sysinfo::{CpuRefreshKind, System};
+use sysinfo::{CpuRefreshKind, Disks, System};
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
@@ -21,13 +23,23 @@ pub const EVENT_TYPE_CREATED: &str = "created";
 pub const EVENT_TYPE_MODIFIED: &str = "modified";
 pub const EVENT_TYPE_DELETED: &str = "deleted";
 pub const EVENT_TYPE_INITIAL: &str = "initial";
+pub const EVENT_TYPE_MANUAL: &str = "manual";
+/// Queued by `force_upload`, bypassing the ignore settings
+pub const EVENT_TYPE_FORCED: &str = "forced";
+const EVENT_TYPE_RENAMED: &str = "renamed";
 const EVENT_TYPE_OTHER: &str = "other";
 
+/// How often each watched folder is checked for having been deleted or unmounted
+const WATCH_ROOT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 // Memory conversion constant
 const BYTES_TO_GB_DIVISOR: u64 = 1024 * 1024 * 1024;
 
+mod error;
+use error::AppError;
+
 mod http_client;
-use http_client::{create_shared_client, SharedHttpClient};
+use http_client::{create_shared_client, header_map, SharedHttpClient};
 
 fn show_main_window(window: &WebviewWindow) {
     let _ = window.unminimize();
@@ -37,29 +49,135 @@ fn show_main_window(window: &WebviewWindow) {
 
 mod upload;
 use upload::{
-    add_to_upload_queue_sync, add_to_upload_queue_with_event_type, clear_session_context,
-    clear_upload_queue, get_org_members, get_queue_size, get_session_context, get_upload_config,
-    get_upload_progress, process_upload_queue, restore_session_context, set_session_context,
-    set_upload_config, trigger_manual_upload, SessionContext, SessionContextState, UploadConfig,
-    UploadConfigState, UploadProgress, UploadProgressState, UploadQueue,
+    add_to_upload_queue_sync, add_to_upload_queue_with_event_type, cancel_upload,
+    clear_session_context, clear_upload_queue, force_upload, get_all_file_statuses, get_auth_token,
+    get_failed_uploads, get_last_sync_times, get_org_members, get_queue_size,
+    get_queued_counts_by_folder, get_session_context, get_upload_config, get_upload_progress,
+    pause_uploads, preview_remote_key, process_upload_queue, reset_progress_counters,
+    restore_last_sync_times, restore_session_context, resume_uploads, retry_failed_uploads,
+    set_session_context, set_upload_config, settings_store, start_processing, test_connection,
+    trigger_manual_upload, trigger_manual_upload_batch, wait_for_processor_start, EnqueueOutcome,
+    FailedUploadsState, FileStatusesState, FolderConfigOverridesState, InFlightUploadsState,
+    LastSyncState, PendingDeletesState, ProcessorStartState, ResumableSessionsState,
+    SessionContext, SessionContextState, SyncedFilesState, UploadChannelsState, UploadConfig,
+    UploadConfigOverride, UploadConfigState, UploadPausedState, UploadProgress,
+    UploadProgressState, UploadQueue,
 };
 
 mod heartbeat;
 use heartbeat::{
-    get_heartbeat_status, start_heartbeat, stop_heartbeat, update_heartbeat_config,
-    HeartbeatConfig, HeartbeatState, HeartbeatStatus, HeartbeatStatusState, HeartbeatTaskState,
+    get_heartbeat_status, start_heartbeat, stop_heartbeat, trigger_heartbeat,
+    update_heartbeat_config, HeartbeatConfig, HeartbeatState, HeartbeatStatus,
+    HeartbeatStatusState, HeartbeatTaskState,
 };
 
+mod webhook;
+
+mod activity;
+use activity::{get_recent_events, ActivityLogState};
+
+mod audit;
+
+mod auth;
+
+mod gitignore;
+use gitignore::GitignoreCacheState;
+
+mod power;
+
 mod diagnostics;
 use diagnostics::run_network_diagnostics;
 
+mod metrics;
+use metrics::get_metrics_text;
+
 #[derive(Clone, Serialize, Deserialize)]
 struct FileChangeEvent {
     path: String,
     event_type: String,
+    /// Previous path, for renames
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_path: Option<String>,
     timestamp: u64,
 }
 
+/// File changes waiting to be emitted as the next `file_changes_batch`.
+type FileChangeBufferState = Arc<Mutex<Vec<FileChangeEvent>>>;
+
+/// Send a file change to the frontend. Changes are buffered for `file_change_batch_ms` and
+/// emitted together, so bulk operations like unzipping don't flood the webview.
+fn emit_file_change(app_handle: &AppHandle, file_change: FileChangeEvent) {
+    let (batch_ms, per_file) = match app_handle.try_state::<UploadConfigState>() {
+        Some(config) => {
+            let config = config.lock();
+            (config.file_change_batch_ms, config.emit_file_change_events)
+        }
+        None => (0, false),
+    };
+    if per_file {
+        let _ = app_handle.emit("file_change", &file_change);
+    }
+    let Some(buffer) = app_handle.try_state::<FileChangeBufferState>() else {
+        return;
+    };
+    if batch_ms == 0 {
+        let _ = app_handle.emit("file_changes_batch", [file_change]);
+        return;
+    }
+
+    let first_in_batch = {
+        let mut buffer = buffer.lock();
+        buffer.push(file_change);
+        buffer.len() == 1
+    };
+    // The change that starts a batch schedules its flush; later ones just join it
+    if first_in_batch {
+        let buffer = buffer.inner().clone();
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(batch_ms)).await;
+            let batch = std::mem::take(&mut *buffer.lock());
+            if !batch.is_empty() {
+                let _ = app_handle.emit("file_changes_batch", &batch);
+            }
+        });
+    }
+}
+
+/// Emitted when a watched folder can no longer be watched, e.g. it was deleted or unmounted.
+#[derive(Clone, Serialize, Deserialize)]
+struct WatchErrorEvent {
+    path: String,
+    reason: String,
+}
+
+/// Work handed from a watcher callback to its queueing task.
+enum WatcherJob {
+    Upload(String),
+    Delete(String),
+    Rename { from: String, to: String },
+}
+
+/// Emitted once the initial scan of a folder has been reconciled against the queue.
+#[derive(Clone, Serialize, Deserialize)]
+struct ReconciliationSummary {
+    folder: String,
+    total_files: usize,
+    queued: usize,
+    unchanged: usize,
+    ignored: usize,
+    errors: usize,
+}
+
+/// Emitted when the initial walk of a folder finishes, after all of its `initial` events.
+#[derive(Clone, Serialize, Deserialize)]
+struct InitialScanComplete {
+    folder: String,
+    total_files: usize,
+    enqueued: usize,
+    ignored: usize,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct DeviceInfo {
     hostname: String,
@@ -71,67 +189,309 @@ struct DeviceInfo {
     os_type: String,
     device_id: String,
     device_fingerprint: String,
+    /// None on machines without a battery
+    battery_percent: Option<f32>,
+    on_ac_power: Option<bool>,
+    /// Volume holding the watched folder (or app data dir); None if it can't be resolved
+    disk_total_bytes: Option<u64>,
+    disk_available_bytes: Option<u64>,
+}
+
+/// Device info computed on first use; only `refresh_device_info` recomputes it.
+type DeviceInfoState = Arc<Mutex<Option<DeviceInfo>>>;
+
+/// A watched folder's watcher, plus the task checking that the folder still exists.
+struct FolderWatch {
+    watcher: RecommendedWatcher,
+    root_monitor: tauri::async_runtime::JoinHandle<()>,
+    /// Set to stop an initial scan still walking the folder
+    scan_cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for FolderWatch {
+    fn drop(&mut self) {
+        self.root_monitor.abort();
+        self.scan_cancelled.store(true, Ordering::Relaxed);
+    }
 }
 
-// Global watcher state
-type WatcherState = Arc<Mutex<Option<RecommendedWatcher>>>;
+// Global watcher state, one watcher per watched folder
+type WatcherState = Arc<Mutex<HashMap<String, FolderWatch>>>;
+
+fn check_folder(folder_path: &str) -> Result<(), AppError> {
+    let path = Path::new(folder_path);
+    if !path.exists() {
+        return Err(AppError::FolderNotFound {
+            path: folder_path.to_string(),
+        });
+    }
+    if !path.is_dir() {
+        return Err(AppError::NotADirectory {
+            path: folder_path.to_string(),
+        });
+    }
+    Ok(())
+}
 
+/// Watch a folder, queueing its existing files and any later changes. `config_override`
+/// replaces some of the global upload settings for this folder only.
 #[tauri::command]
 async fn start_watching(
     folder_path: String,
+    config_override: Option<UploadConfigOverride>,
     app_handle: AppHandle,
     watcher_state: tauri::State<'_, WatcherState>,
     upload_queue: tauri::State<'_, UploadQueue>,
     upload_config: tauri::State<'_, UploadConfigState>,
-) -> Result<String, String> {
-    // Stop any existing watcher
+    folder_config_overrides: tauri::State<'_, FolderConfigOverridesState>,
+) -> Result<String, AppError> {
+    check_folder(&folder_path)?;
+
+    // Re-watching a folder replaces its watcher, so it doesn't count against the limit
     {
-        let mut watcher = watcher_state.lock();
-        *watcher = None;
+        let watchers = watcher_state.lock();
+        let max = upload_config.lock().max_watched_folders;
+        if !watchers.contains_key(&folder_path) && watchers.len() >= max {
+            return Err(AppError::TooManyFolders { max });
+        }
     }
+    upload::set_folder_config_override(
+        &folder_path,
+        config_override,
+        folder_config_overrides.inner(),
+        upload_config.inner(),
+    )?;
 
-    // First, capture initial folder contents and optionally queue for upload
-    capture_initial_contents(
+    // The watcher goes in before the initial scan, so changes during the scan aren't missed
+    let watcher = create_folder_watcher(
         &folder_path,
         &app_handle,
         upload_queue.inner(),
         upload_config.inner(),
     )?;
+    let scan_cancelled = Arc::new(AtomicBool::new(false));
+    let root_monitor = tauri::async_runtime::spawn(monitor_watch_root(
+        folder_path.clone(),
+        app_handle.clone(),
+        upload_queue.inner().clone(),
+        upload_config.inner().clone(),
+        scan_cancelled.clone(),
+    ));
+
+    // Store the watcher
+    watcher_state.lock().insert(
+        folder_path.clone(),
+        FolderWatch {
+            watcher,
+            root_monitor,
+            scan_cancelled: scan_cancelled.clone(),
+        },
+    );
+
+    spawn_initial_scan(
+        folder_path.clone(),
+        app_handle,
+        upload_queue.inner().clone(),
+        upload_config.inner().clone(),
+        scan_cancelled,
+    );
+
+    Ok(format!("Started watching: {folder_path}"))
+}
+
+/// Walk the folder in the background, queueing existing files. Stopping the watch cancels it.
+fn spawn_initial_scan(
+    folder_path: String,
+    app_handle: AppHandle,
+    upload_queue: UploadQueue,
+    upload_config: UploadConfigState,
+    cancelled: Arc<AtomicBool>,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = capture_initial_contents(
+            &folder_path,
+            &folder_path,
+            false,
+            &app_handle,
+            &upload_queue,
+            &upload_config,
+            &cancelled,
+        ) {
+            log::error!("Initial scan of {folder_path} failed: {e}");
+            let _ = app_handle.emit(
+                "watch_error",
+                &WatchErrorEvent {
+                    path: folder_path,
+                    reason: e,
+                },
+            );
+        }
+    });
+}
+
+/// notify stops delivering events once a watched folder is deleted or its drive unmounted,
+/// so poll for the folder's existence: report when it disappears and, when it comes back,
+/// rescan it and replace the dead watcher.
+async fn monitor_watch_root(
+    folder_path: String,
+    app_handle: AppHandle,
+    upload_queue: UploadQueue,
+    upload_config: UploadConfigState,
+    scan_cancelled: Arc<AtomicBool>,
+) {
+    let mut root_missing = false;
+    loop {
+        tokio::time::sleep(WATCH_ROOT_CHECK_INTERVAL).await;
+
+        let exists = Path::new(&folder_path).is_dir();
+        if !exists && !root_missing {
+            root_missing = true;
+            log::warn!("Watched folder {folder_path} is no longer available");
+            let _ = app_handle.emit(
+                "watch_error",
+                &WatchErrorEvent {
+                    path: folder_path.clone(),
+                    reason: "Folder was deleted, moved or unmounted".to_string(),
+                },
+            );
+        } else if exists && root_missing {
+            match create_folder_watcher(&folder_path, &app_handle, &upload_queue, &upload_config)
+            {
+                Ok(watcher) => {
+                    root_missing = false;
+                    log::info!("Watched folder {folder_path} is available again, resumed watching");
+                    if let Some(watchers) = app_handle.try_state::<WatcherState>() {
+                        if let Some(folder_watch) = watchers.lock().get_mut(&folder_path) {
+                            folder_watch.watcher = watcher;
+                        }
+                    }
+                    spawn_initial_scan(
+                        folder_path.clone(),
+                        app_handle.clone(),
+                        upload_queue.clone(),
+                        upload_config.clone(),
+                        scan_cancelled.clone(),
+                    );
+                }
+                Err(e) => log::warn!("Failed to re-establish watch on {folder_path}: {e}"),
+            }
+        }
+    }
+}
+
+/// Create a watcher queueing changed files for upload.
+fn create_folder_watcher(
+    folder_path: &str,
+    app_handle: &AppHandle,
+    upload_queue: &UploadQueue,
+    upload_config: &UploadConfigState,
+) -> Result<RecommendedWatcher, String> {
+    let max_watch_depth =
+        upload::effective_upload_config(folder_path, upload_config, app_handle).max_watch_depth;
 
     let app_handle_clone = app_handle.clone();
-    let upload_queue_clone = upload_queue.inner().clone();
-    let upload_config_clone = upload_config.inner().clone();
-    let folder_path_clone = folder_path.clone();
+    let upload_queue_clone = upload_queue.clone();
+    let upload_config_clone = upload_config.clone();
 
     // Channel to move work off the watcher callback thread so it never blocks
-    let (watcher_tx, mut watcher_rx) =
-        tokio::sync::mpsc::unbounded_channel::<(String, String)>();
+    let (watcher_tx, mut watcher_rx) = tokio::sync::mpsc::unbounded_channel::<WatcherJob>();
 
     // Spawn a task that drains the channel and queues uploads without blocking the watcher
     {
         let queue = upload_queue_clone.clone();
         let config = upload_config_clone.clone();
         let app = app_handle.clone();
+        let base_path = folder_path.to_string();
+        // Renames waiting out `rename_coalesce_ms` to be handled together
+        let pending_renames: Arc<Mutex<Vec<(String, String)>>> = Arc::default();
         tauri::async_runtime::spawn(async move {
-            while let Some((file_path, base_path)) = watcher_rx.recv().await {
-                add_to_upload_queue_sync(file_path, base_path, &queue, &config, &app);
+            while let Some(job) = watcher_rx.recv().await {
+                match job {
+                    WatcherJob::Upload(file_path) => {
+                        add_to_upload_queue_sync(
+                            file_path,
+                            base_path.clone(),
+                            &queue,
+                            &config,
+                            &app,
+                        );
+                    }
+                    WatcherJob::Delete(file_path) => {
+                        upload::queue_delete(file_path, base_path.clone(), &config, &app);
+                    }
+                    WatcherJob::Rename { from, to } => {
+                        let first = {
+                            let mut pending = pending_renames.lock();
+                            pending.push((from, to));
+                            pending.len() == 1
+                        };
+                        if !first {
+                            continue;
+                        }
+                        let pending_renames = pending_renames.clone();
+                        let queue = queue.clone();
+                        let config = config.clone();
+                        let app = app.clone();
+                        let base_path = base_path.clone();
+                        tauri::async_runtime::spawn(async move {
+                            let window = config.lock().rename_coalesce_ms;
+                            tokio::time::sleep(std::time::Duration::from_millis(window)).await;
+                            let renames = std::mem::take(&mut *pending_renames.lock());
+                            upload::handle_renames(renames, base_path, &queue, &config, &app).await;
+                        });
+                    }
+                }
             }
         });
     }
 
     // Create file watcher — callback only emits the event and sends to the channel,
     // never blocks on queue/config locks
+    let watched_folder = folder_path.to_string();
     let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         let event = match res {
             Ok(event) => event,
             Err(e) => {
                 log::error!("File watcher error: {e}");
+                let _ = app_handle_clone.emit(
+                    "watch_error",
+                    &WatchErrorEvent {
+                        path: watched_folder.clone(),
+                        reason: e.to_string(),
+                    },
+                );
                 return;
             }
         };
 
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        // Renames reported with both paths are handled as moves. Elsewhere they arrive as
+        // separate halves and fall back to delete + create.
+        if let notify::EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+            if let [from, to] = event.paths.as_slice() {
+                let file_change = FileChangeEvent {
+                    path: to.to_string_lossy().to_string(),
+                    event_type: EVENT_TYPE_RENAMED.to_string(),
+                    old_path: Some(from.to_string_lossy().to_string()),
+                    timestamp,
+                };
+                let _ = watcher_tx.send(WatcherJob::Rename {
+                    from: file_change.old_path.clone().unwrap_or_default(),
+                    to: file_change.path.clone(),
+                });
+                emit_file_change(&app_handle_clone, file_change);
+                return;
+            }
+        }
+
         let event_type = match event.kind {
             notify::EventKind::Create(_) => EVENT_TYPE_CREATED,
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::From)) => EVENT_TYPE_DELETED,
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::To)) => EVENT_TYPE_CREATED,
             notify::EventKind::Modify(_) => EVENT_TYPE_MODIFIED,
             notify::EventKind::Remove(_) => EVENT_TYPE_DELETED,
             _ => EVENT_TYPE_OTHER,
@@ -141,86 +501,289 @@ async fn start_watching(
             let file_change = FileChangeEvent {
                 path: path.to_string_lossy().to_string(),
                 event_type: event_type.to_string(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                old_path: None,
+                timestamp,
             };
 
-            // Send to frontend immediately — never blocked by queue locks
-            let _ = app_handle_clone.emit("file_change", &file_change);
+            emit_file_change(&app_handle_clone, file_change);
 
             // Queue for upload via channel (non-blocking send)
             if event_type == EVENT_TYPE_CREATED || event_type == EVENT_TYPE_MODIFIED {
                 let file_path = path.to_string_lossy().to_string();
-                let _ = watcher_tx.send((file_path, folder_path_clone.clone()));
+                let _ = watcher_tx.send(WatcherJob::Upload(file_path));
+            } else if event_type == EVENT_TYPE_DELETED {
+                let file_path = path.to_string_lossy().to_string();
+                let _ = watcher_tx.send(WatcherJob::Delete(file_path));
             }
         }
     })
     .map_err(|e| format!("Failed to create watcher: {e}"))?;
 
-    // Start watching the folder
+    // Start watching the folder. With a depth limit, the initial scan gives each directory it
+    // reaches its own non-recursive watch instead, so nothing deeper is watched at all.
+    let recursive_mode = if max_watch_depth.is_some() {
+        RecursiveMode::NonRecursive
+    } else {
+        RecursiveMode::Recursive
+    };
     watcher
-        .watch(Path::new(&folder_path), RecursiveMode::Recursive)
+        .watch(Path::new(folder_path), recursive_mode)
         .map_err(|e| format!("Failed to watch folder: {e}"))?;
 
-    // Store the watcher
-    {
-        let mut watcher_state = watcher_state.lock();
-        *watcher_state = Some(watcher);
-    }
+    Ok(watcher)
+}
 
-    Ok(format!("Started watching: {folder_path}"))
+/// Add a non-recursive watch on a subdirectory of a folder watched with a depth limit.
+fn watch_subdirectory(folder_path: &str, dir: &Path, app_handle: &AppHandle) {
+    let Some(watchers) = app_handle.try_state::<WatcherState>() else {
+        return;
+    };
+    let mut watchers = watchers.lock();
+    if let Some(folder_watch) = watchers.get_mut(folder_path) {
+        if let Err(e) = folder_watch.watcher.watch(dir, RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch folder {dir:?}: {e}");
+        }
+    }
 }
 
+/// Walk `scan_root`, the watched folder or a subfolder of it, on several threads, queueing
+/// existing files, until done or `cancelled` is set. A `rescan` queues files even with
+/// `ignore_existing_files` set and doesn't report the folder's initial scan as complete.
+/// Returns how many files were queued.
 fn capture_initial_contents(
     folder_path: &str,
+    scan_root: &str,
+    rescan: bool,
     app_handle: &AppHandle,
     upload_queue: &UploadQueue,
     upload_config: &UploadConfigState,
-) -> Result<(), String> {
-    let mut dirs_to_visit = vec![PathBuf::from(folder_path)];
+    cancelled: &AtomicBool,
+) -> Result<usize, String> {
+    let config = upload::effective_upload_config(folder_path, upload_config, app_handle);
+    let (max_watch_depth, follow_symlinks) = (config.max_watch_depth, config.follow_symlinks);
+    let skip_existing = config.ignore_existing_files && !rescan;
+    // Fail outright only if the folder itself can't be read; unreadable subdirectories are
+    // counted as errors
+    fs::read_dir(scan_root).map_err(|e| format!("Failed to read directory {scan_root:?}: {e}"))?;
+    // Depths below are counted from `scan_root`, the depth limit from `folder_path`
+    let root_depth = Path::new(scan_root)
+        .strip_prefix(folder_path)
+        .map_or(0, |relative| relative.components().count());
+
+    let summary = Mutex::new(ReconciliationSummary {
+        folder: folder_path.to_string(),
+        total_files: 0,
+        queued: 0,
+        unchanged: 0,
+        ignored: 0,
+        errors: 0,
+    });
 
-    while let Some(dir) = dirs_to_visit.pop() {
-        let entries =
-            fs::read_dir(&dir).map_err(|e| format!("Failed to read directory {dir:?}: {e}"))?;
+    // Our own ignore handling applies, not the walker's. A directory `max_watch_depth` levels
+    // down is still listed, just not entered.
+    WalkBuilder::new(scan_root)
+        .standard_filters(false)
+        .follow_links(follow_symlinks)
+        .max_depth(max_watch_depth.map(|max_depth| (max_depth + 1).saturating_sub(root_depth)))
+        .build_parallel()
+        .run(|| {
+            let (summary, config) = (&summary, &config);
+            Box::new(move |entry| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
+                }
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        log::warn!("Initial scan of {scan_root}: {e}");
+                        summary.lock().errors += 1;
+                        return WalkState::Continue;
+                    }
+                };
+                // The folder itself
+                if entry.depth() == 0 {
+                    return WalkState::Continue;
+                }
+                if !follow_symlinks && entry.path_is_symlink() {
+                    log::info!("Initial scan skipping symlink {}", entry.path().display());
+                    summary.lock().ignored += 1;
+                    return WalkState::Continue;
+                }
 
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let file_change = FileChangeEvent {
-                path: path.to_string_lossy().to_string(),
-                event_type: EVENT_TYPE_INITIAL.to_string(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            };
-            let _ = app_handle.emit("file_change", &file_change);
-
-            if path.is_dir() {
-                let relative_path = upload::get_relative_path(&path.to_string_lossy(), folder_path);
-                upload::emit_file_upload_status(&relative_path, upload::STATUS_DIRECTORY, None, app_handle);
-                dirs_to_visit.push(path);
-            } else {
-                add_to_upload_queue_with_event_type(
-                    path.to_string_lossy().to_string(),
-                    folder_path.to_string(),
-                    upload_queue,
-                    upload_config,
-                    EVENT_TYPE_INITIAL,
-                    app_handle,
-                );
-            }
-        }
+                let path = entry.path();
+                let file_change = FileChangeEvent {
+                    path: path.to_string_lossy().to_string(),
+                    event_type: EVENT_TYPE_INITIAL.to_string(),
+                    old_path: None,
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                };
+                emit_file_change(app_handle, file_change);
+
+                if entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_dir())
+                {
+                    let relative_path =
+                        upload::get_relative_path(&path.to_string_lossy(), folder_path);
+                    upload::emit_file_upload_status(
+                        &relative_path,
+                        upload::STATUS_DIRECTORY,
+                        None,
+                        app_handle,
+                    );
+                    // Nothing under an ignored directory can be uploaded, so don't walk it
+                    if upload::is_excluded_by_ignore_files(
+                        &path.to_string_lossy(),
+                        folder_path,
+                        config,
+                        app_handle,
+                    ) {
+                        return WalkState::Skip;
+                    }
+                    if max_watch_depth
+                        .is_some_and(|max_depth| root_depth + entry.depth() <= max_depth)
+                    {
+                        watch_subdirectory(folder_path, path, app_handle);
+                    }
+                } else if skip_existing {
+                    log::debug!(
+                        "Ignoring existing file due to ignore_existing_files setting: {}",
+                        path.display()
+                    );
+                    let relative_path =
+                        upload::get_relative_path(&path.to_string_lossy(), folder_path);
+                    upload::emit_file_upload_status(
+                        &relative_path,
+                        upload::STATUS_IGNORED,
+                        None,
+                        app_handle,
+                    );
+                    let mut summary = summary.lock();
+                    summary.total_files += 1;
+                    summary.ignored += 1;
+                } else {
+                    let outcome = add_to_upload_queue_with_event_type(
+                        path.to_string_lossy().to_string(),
+                        folder_path.to_string(),
+                        upload_queue,
+                        upload_config,
+                        EVENT_TYPE_INITIAL,
+                        app_handle,
+                    );
+                    let mut summary = summary.lock();
+                    summary.total_files += 1;
+                    match outcome {
+                        EnqueueOutcome::Queued => summary.queued += 1,
+                        EnqueueOutcome::Unchanged => summary.unchanged += 1,
+                        EnqueueOutcome::Ignored | EnqueueOutcome::Directory => summary.ignored += 1,
+                        EnqueueOutcome::QueueFull | EnqueueOutcome::Error => summary.errors += 1,
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+
+    if cancelled.load(Ordering::Relaxed) {
+        log::info!("Initial scan of {scan_root} cancelled");
+        return Ok(0);
     }
 
-    Ok(())
+    let summary = summary.into_inner();
+    if rescan {
+        log::info!("Rescan of {scan_root} queued {} files", summary.queued);
+        return Ok(summary.queued);
+    }
+    log::info!(
+        "Initial scan of {folder_path} reconciled: {} files, {} queued, {} unchanged, {} ignored",
+        summary.total_files,
+        summary.queued,
+        summary.unchanged,
+        summary.ignored
+    );
+    let _ = app_handle.emit("reconciliation_summary", &summary);
+    let _ = app_handle.emit(
+        "initial_scan_complete",
+        &InitialScanComplete {
+            folder: summary.folder,
+            total_files: summary.total_files,
+            enqueued: summary.queued,
+            ignored: summary.ignored,
+        },
+    );
+
+    Ok(summary.queued)
 }
 
 #[tauri::command]
-async fn stop_watching(watcher_state: tauri::State<'_, WatcherState>) -> Result<String, String> {
-    let mut watcher = watcher_state.lock();
-    *watcher = None;
+async fn stop_watching_folder(
+    folder_path: String,
+    watcher_state: tauri::State<'_, WatcherState>,
+    folder_config_overrides: tauri::State<'_, FolderConfigOverridesState>,
+) -> Result<String, AppError> {
+    folder_config_overrides.lock().remove(&folder_path);
+    upload::forget_canonical_base_path(&folder_path);
+    match watcher_state.lock().remove(&folder_path) {
+        Some(_) => Ok(format!("Stopped watching: {folder_path}")),
+        None => Err(AppError::FolderNotWatched { path: folder_path }),
+    }
+}
+
+#[tauri::command]
+fn get_watched_folders(
+    watcher_state: tauri::State<'_, WatcherState>,
+) -> Result<Vec<String>, AppError> {
+    let mut folders: Vec<String> = watcher_state.lock().keys().cloned().collect();
+    folders.sort();
+    Ok(folders)
+}
+
+/// Queue every file under `folder_path`, a subfolder of the watched `base_path`, as the initial
+/// scan would, but regardless of `ignore_existing_files`. What was synced under it is
+/// forgotten first, so each file is checked with the server again. Returns how many were
+/// queued.
+#[tauri::command]
+async fn rescan_folder(
+    folder_path: String,
+    base_path: String,
+    app_handle: AppHandle,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    upload_config: tauri::State<'_, UploadConfigState>,
+) -> Result<usize, AppError> {
+    if !Path::new(&folder_path).starts_with(&base_path) {
+        return Err(format!("{folder_path} is not inside {base_path}").into());
+    }
+    check_folder(&folder_path)?;
+
+    upload::forget_synced_files_under(&folder_path, &app_handle);
+    let upload_queue = upload_queue.inner().clone();
+    let upload_config = upload_config.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        capture_initial_contents(
+            &base_path,
+            &folder_path,
+            true,
+            &app_handle,
+            &upload_queue,
+            &upload_config,
+            &AtomicBool::new(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Rescan failed: {e}"))?
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn stop_watching(
+    watcher_state: tauri::State<'_, WatcherState>,
+    folder_config_overrides: tauri::State<'_, FolderConfigOverridesState>,
+) -> Result<String, AppError> {
+    watcher_state.lock().clear();
+    folder_config_overrides.lock().clear();
+    upload::forget_canonical_base_paths();
     Ok("Stopped watching".to_string())
 }
 
@@ -238,7 +801,7 @@ fn get_device_id_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join(DEVICE_ID_FILENAME))
 }
 
-fn get_device_id(app_handle: &AppHandle) -> Result<String, String> {
+fn load_device_id(app_handle: &AppHandle) -> Result<String, String> {
     let id_file_path = get_device_id_path(app_handle)?;
 
     if id_file_path.exists() {
@@ -251,7 +814,7 @@ fn get_device_id(app_handle: &AppHandle) -> Result<String, String> {
     }
 }
 
-fn get_device_fingerprint() -> Result<String, String> {
+fn compute_device_fingerprint() -> Result<String, String> {
     let machine_id = machine_uid::get().map_err(|e| format!("Failed to get machine ID: {e}"))?;
 
     let mut hasher = Sha256::new();
@@ -261,14 +824,13 @@ fn get_device_fingerprint() -> Result<String, String> {
     Ok(format!("{result:x}"))
 }
 
-#[tauri::command]
-fn get_device_info(app_handle: AppHandle) -> Result<DeviceInfo, String> {
+fn compute_device_info(app_handle: &AppHandle) -> Result<DeviceInfo, String> {
     let mut sys = System::new();
     sys.refresh_cpu_list(CpuRefreshKind::default());
     sys.refresh_memory();
 
-    let device_id = get_device_id(&app_handle)?;
-    let device_fingerprint = get_device_fingerprint()?;
+    let device_id = load_device_id(app_handle)?;
+    let device_fingerprint = compute_device_fingerprint()?;
 
     let hostname = System::host_name().unwrap_or_else(|| "Unknown".to_string());
 
@@ -294,7 +856,7 @@ fn get_device_info(app_handle: AppHandle) -> Result<DeviceInfo, String> {
 
     let release = System::os_version().unwrap_or_else(|| "Unknown".to_string());
 
-    Ok(DeviceInfo {
+    let mut device_info = DeviceInfo {
         hostname,
         platform: platform.to_string(),
         release,
@@ -304,6 +866,108 @@ fn get_device_info(app_handle: AppHandle) -> Result<DeviceInfo, String> {
         os_type: platform.to_string(),
         device_id,
         device_fingerprint,
+        battery_percent: None,
+        on_ac_power: None,
+        disk_total_bytes: None,
+        disk_available_bytes: None,
+    };
+    refresh_volatile_device_info(&mut device_info, app_handle);
+    Ok(device_info)
+}
+
+/// Update the fields that change too often to cache: power and disk space.
+fn refresh_volatile_device_info(device_info: &mut DeviceInfo, app_handle: &AppHandle) {
+    let power = power::read_power_state();
+    device_info.battery_percent = power.battery_percent;
+    device_info.on_ac_power = power.on_ac_power;
+
+    let disk_path = app_handle
+        .try_state::<WatcherState>()
+        .and_then(|watchers| watchers.lock().keys().min().map(PathBuf::from))
+        .or_else(|| app_handle.path().app_data_dir().ok());
+    let disk_space = disk_path.and_then(|path| disk_space_for(&path));
+    device_info.disk_total_bytes = disk_space.map(|(total, _)| total);
+    device_info.disk_available_bytes = disk_space.map(|(_, available)| available);
+}
+
+/// Total and available bytes on the volume containing `path`, found by the longest
+/// matching mount point.
+fn disk_space_for(path: &Path) -> Option<(u64, u64)> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| (disk.total_space(), disk.available_space()))
+}
+
+fn cached_device_info(
+    app_handle: &AppHandle,
+    device_info_state: &DeviceInfoState,
+) -> Result<DeviceInfo, String> {
+    if let Some(device_info) = device_info_state.lock().as_ref() {
+        return Ok(device_info.clone());
+    }
+    let device_info = compute_device_info(app_handle)?;
+    *device_info_state.lock() = Some(device_info.clone());
+    Ok(device_info)
+}
+
+#[tauri::command]
+fn get_device_info(
+    app_handle: AppHandle,
+    device_info_state: tauri::State<'_, DeviceInfoState>,
+) -> Result<DeviceInfo, AppError> {
+    let mut device_info = cached_device_info(&app_handle, device_info_state.inner())?;
+    refresh_volatile_device_info(&mut device_info, &app_handle);
+    Ok(device_info)
+}
+
+/// Just the device id, without gathering the rest of the device info.
+#[tauri::command]
+fn get_device_id(app_handle: AppHandle) -> Result<String, AppError> {
+    Ok(load_device_id(&app_handle)?)
+}
+
+/// Just the device fingerprint, without gathering the rest of the device info.
+#[tauri::command]
+fn get_device_fingerprint() -> Result<String, AppError> {
+    Ok(compute_device_fingerprint()?)
+}
+
+#[tauri::command]
+fn refresh_device_info(
+    app_handle: AppHandle,
+    device_info_state: tauri::State<'_, DeviceInfoState>,
+) -> Result<DeviceInfo, AppError> {
+    let device_info = compute_device_info(&app_handle)?;
+    *device_info_state.lock() = Some(device_info.clone());
+    Ok(device_info)
+}
+
+/// Heartbeat config for the endpoint at `path` on the configured server.
+fn build_heartbeat_config(
+    path: String,
+    token: String,
+    initial_delay_secs: u64,
+    upload_config: &UploadConfig,
+    app_handle: &AppHandle,
+    device_info_state: &DeviceInfoState,
+) -> Result<HeartbeatConfig, String> {
+    let device_info = cached_device_info(app_handle, device_info_state)?;
+    let extra_headers = header_map(&upload_config.heartbeat_extra_headers)
+        .map_err(|e| format!("Invalid heartbeat header: {e}"))?;
+
+    Ok(HeartbeatConfig {
+        url: format!("{}{path}", upload_config.server_url),
+        path,
+        token,
+        device_fingerprint: device_info.device_fingerprint,
+        app_version: app_handle.package_info().version.to_string(),
+        extra_headers,
+        initial_delay_secs,
     })
 }
 
@@ -312,31 +976,24 @@ fn get_device_info(app_handle: AppHandle) -> Result<DeviceInfo, String> {
 async fn start_heartbeat_service(
     url: String,
     token: String,
+    initial_delay_secs: Option<u64>,
     app_handle: AppHandle,
     http_client: tauri::State<'_, SharedHttpClient>,
     heartbeat_state: tauri::State<'_, HeartbeatState>,
     heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
     heartbeat_task_state: tauri::State<'_, HeartbeatTaskState>,
     upload_config: tauri::State<'_, UploadConfigState>,
-) -> Result<String, String> {
-    // Get device info to build heartbeat config
-    let device_info = get_device_info(app_handle.clone())?;
-    let app_version = app_handle.package_info().version.to_string();
-
-    // Get server URL from upload config
-    let server_url = {
-        let config = upload_config.lock();
-        config.server_url.clone()
-    };
-
-    let full_url = format!("{server_url}{url}");
-
-    let config = HeartbeatConfig {
-        url: full_url,
+    device_info_state: tauri::State<'_, DeviceInfoState>,
+) -> Result<String, AppError> {
+    let upload_config = upload_config.lock().clone();
+    let config = build_heartbeat_config(
+        url,
         token,
-        device_fingerprint: device_info.device_fingerprint,
-        app_version,
-    };
+        initial_delay_secs.unwrap_or_default(),
+        &upload_config,
+        &app_handle,
+        device_info_state.inner(),
+    )?;
 
     start_heartbeat(
         config,
@@ -356,7 +1013,7 @@ async fn stop_heartbeat_service(
     heartbeat_state: tauri::State<'_, HeartbeatState>,
     heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
     heartbeat_task_state: tauri::State<'_, HeartbeatTaskState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     stop_heartbeat(
         heartbeat_state.inner().clone(),
         heartbeat_status_state.inner().clone(),
@@ -367,13 +1024,61 @@ async fn stop_heartbeat_service(
     Ok("Heartbeat stopped".to_string())
 }
 
+/// Suspend heartbeats, e.g. during a maintenance window. `resume_heartbeat` restarts them.
+#[tauri::command]
+async fn pause_heartbeat(
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    heartbeat::pause_heartbeat(
+        heartbeat_state.inner().clone(),
+        heartbeat_status_state.inner().clone(),
+        app_handle,
+    )
+    .await
+}
+
+#[tauri::command]
+async fn resume_heartbeat(
+    http_client: tauri::State<'_, SharedHttpClient>,
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    heartbeat::resume_heartbeat(
+        http_client.inner().clone(),
+        heartbeat_state.inner().clone(),
+        heartbeat_status_state.inner().clone(),
+        app_handle,
+    )
+    .await
+}
+
 #[tauri::command]
 async fn get_heartbeat_status_command(
     heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
-) -> Result<HeartbeatStatus, String> {
+) -> Result<HeartbeatStatus, AppError> {
     Ok(get_heartbeat_status(heartbeat_status_state.inner().clone()).await)
 }
 
+/// Send a heartbeat immediately and return its result, for a manual health check.
+#[tauri::command]
+async fn send_heartbeat_now(
+    http_client: tauri::State<'_, SharedHttpClient>,
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    app_handle: AppHandle,
+) -> Result<HeartbeatStatus, AppError> {
+    trigger_heartbeat(
+        http_client.inner().clone(),
+        heartbeat_state.inner().clone(),
+        heartbeat_status_state.inner().clone(),
+        app_handle,
+    )
+    .await
+}
+
 #[tauri::command]
 async fn update_heartbeat_token(
     new_token: String,
@@ -382,7 +1087,7 @@ async fn update_heartbeat_token(
     heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
     heartbeat_task_state: tauri::State<'_, HeartbeatTaskState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let current_config = {
         let state = heartbeat_state.inner().lock().await;
         state.clone()
@@ -401,25 +1106,208 @@ async fn update_heartbeat_token(
         .await?;
         Ok("Heartbeat token updated".to_string())
     } else {
-        Err("No active heartbeat to update".to_string())
+        Err(AppError::NoActiveHeartbeat)
+    }
+}
+
+/// Switch uploads to another server, and a running heartbeat along with them.
+#[tauri::command]
+async fn update_server_url(
+    url: String,
+    http_client: tauri::State<'_, SharedHttpClient>,
+    upload_config: tauri::State<'_, UploadConfigState>,
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    heartbeat_task_state: tauri::State<'_, HeartbeatTaskState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    upload::set_server_url(url.clone(), upload_config.inner())?;
+
+    let current_config = {
+        let state = heartbeat_state.inner().lock().await;
+        state.clone()
+    };
+    if let Some(mut config) = current_config {
+        config.url = format!("{url}{}", config.path);
+        update_heartbeat_config(
+            config,
+            http_client.inner().clone(),
+            heartbeat_state.inner().clone(),
+            heartbeat_status_state.inner().clone(),
+            heartbeat_task_state.inner().clone(),
+            app_handle,
+        )
+        .await?;
+    }
+    Ok(format!("Server URL updated to {url}"))
+}
+
+/// Heartbeat settings carried by an exported config.
+#[derive(Serialize, Deserialize)]
+struct ExportedHeartbeat {
+    /// Endpoint path, appended to `server_url`
+    path: String,
+    initial_delay_secs: u64,
+}
+
+/// Settings from `export_config`, for setting up another device the same way.
+#[derive(Serialize, Deserialize)]
+struct ExportedConfig {
+    upload: UploadConfig,
+    /// None if no heartbeat was running
+    heartbeat: Option<ExportedHeartbeat>,
+    /// Whether the token and the secret upload settings (see `copy_secrets_from`) were
+    /// exported. Without them, importing keeps the device's own.
+    #[serde(default)]
+    secrets_included: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+/// Upload and heartbeat settings as JSON. The auth token, encryption key, extra headers
+/// and webhook URLs are left out unless `include_secrets` is set.
+#[tauri::command]
+async fn export_config(
+    include_secrets: bool,
+    upload_config: tauri::State<'_, UploadConfigState>,
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    let mut upload = upload_config.lock().clone();
+    let heartbeat = heartbeat_state
+        .inner()
+        .lock()
+        .await
+        .as_ref()
+        .map(|config| ExportedHeartbeat {
+            path: config.path.clone(),
+            initial_delay_secs: config.initial_delay_secs,
+        });
+    let token = if include_secrets {
+        get_auth_token(&app_handle)?
+    } else {
+        upload.copy_secrets_from(&UploadConfig::default());
+        None
+    };
+
+    let exported = ExportedConfig {
+        upload,
+        heartbeat,
+        secrets_included: include_secrets,
+        token,
+    };
+    serde_json::to_string_pretty(&exported)
+        .map_err(|e| format!("Failed to serialize config: {e}").into())
+}
+
+/// Apply settings from `export_config`. Everything that can fail is checked before anything
+/// changes, and the config and token are put back if the heartbeat can't be restarted.
+/// Secrets missing from `json` are kept as they are on this device. A heartbeat is
+/// (re)started if the export had one or one is already running.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn import_config(
+    json: String,
+    http_client: tauri::State<'_, SharedHttpClient>,
+    upload_config: tauri::State<'_, UploadConfigState>,
+    heartbeat_state: tauri::State<'_, HeartbeatState>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    heartbeat_task_state: tauri::State<'_, HeartbeatTaskState>,
+    device_info_state: tauri::State<'_, DeviceInfoState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    let imported: ExportedConfig = serde_json::from_str(&json).map_err(|e| AppError::Config {
+        fields: vec![],
+        message: format!("Invalid config: {e}"),
+    })?;
+    let mut upload = imported.upload;
+    if !imported.secrets_included {
+        upload.copy_secrets_from(&upload_config.lock());
+    }
+    upload.validate()?;
+
+    let store = settings_store(&app_handle)?;
+    let previous_token = store.get("token");
+    let current_heartbeat = heartbeat_state.inner().lock().await.clone();
+    let heartbeat_settings = match (imported.heartbeat, &current_heartbeat) {
+        (Some(heartbeat), _) => Some((heartbeat.path, heartbeat.initial_delay_secs)),
+        (None, Some(current)) => Some((current.path.clone(), current.initial_delay_secs)),
+        (None, None) => None,
+    };
+    let token = match (&imported.token, current_heartbeat) {
+        (Some(token), _) => Some(token.clone()),
+        (None, Some(current)) => Some(current.token),
+        (None, None) => previous_token
+            .as_ref()
+            .and_then(|token| token.as_str().map(String::from)),
+    };
+    let heartbeat = match (heartbeat_settings, token) {
+        (Some((path, initial_delay_secs)), Some(token)) => Some(build_heartbeat_config(
+            path,
+            token,
+            initial_delay_secs,
+            &upload,
+            &app_handle,
+            device_info_state.inner(),
+        )?),
+        (Some(_), None) => {
+            log::warn!("Imported config has a heartbeat, but there's no token to start it with");
+            None
+        }
+        (None, _) => None,
+    };
+
+    let previous_upload = std::mem::replace(&mut *upload_config.lock(), upload);
+    if let Some(token) = &imported.token {
+        store.set("token", serde_json::Value::String(token.clone()));
+    }
+    if let Some(heartbeat) = heartbeat {
+        if let Err(e) = start_heartbeat(
+            heartbeat,
+            http_client.inner().clone(),
+            heartbeat_state.inner().clone(),
+            heartbeat_status_state.inner().clone(),
+            heartbeat_task_state.inner().clone(),
+            app_handle,
+        )
+        .await
+        {
+            *upload_config.lock() = previous_upload;
+            match previous_token {
+                Some(token) => store.set("token", token),
+                None => {
+                    store.delete("token");
+                }
+            }
+            return Err(format!("Failed to restart heartbeat, import reverted: {e}").into());
+        }
     }
+    Ok("Configuration imported".to_string())
 }
 
 struct QuitFlag(AtomicBool);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let watcher_state: WatcherState = Arc::new(Mutex::new(None));
+    let watcher_state: WatcherState = Arc::new(Mutex::new(HashMap::new()));
+    let gitignore_cache: GitignoreCacheState = Arc::new(Mutex::new(HashMap::new()));
+    let device_info_state: DeviceInfoState = Arc::new(Mutex::new(None));
+    let failed_uploads: FailedUploadsState = Arc::new(Mutex::new(HashMap::new()));
+    let file_statuses: FileStatusesState = Arc::new(Mutex::new(HashMap::new()));
+    let activity_log: ActivityLogState = Arc::new(Mutex::new(VecDeque::new()));
+    let resumable_sessions: ResumableSessionsState = Arc::new(Mutex::new(HashMap::new()));
+    let pending_deletes: PendingDeletesState = Arc::new(Mutex::new(HashMap::new()));
+    let folder_config_overrides: FolderConfigOverridesState = Arc::new(Mutex::new(HashMap::new()));
     let upload_queue: UploadQueue = Arc::new(Mutex::new(VecDeque::new()));
     let upload_config: UploadConfigState = Arc::new(Mutex::new(UploadConfig::default()));
-    let upload_progress: UploadProgressState = Arc::new(Mutex::new(UploadProgress {
-        total_queued: 0,
-        total_uploaded: 0,
-        total_failed: 0,
-        in_flight: 0,
-        current_uploading: None,
-    }));
+    let upload_progress: UploadProgressState = Arc::new(Mutex::new(UploadProgress::default()));
+    let upload_paused: UploadPausedState = Arc::new(Mutex::new(false));
+    let in_flight_uploads: InFlightUploadsState = Arc::new(Mutex::new(HashMap::new()));
+    let upload_channels: UploadChannelsState = Arc::new(Mutex::new(HashMap::new()));
     let session_context: SessionContextState = Arc::new(Mutex::new(SessionContext::default()));
+    let synced_files: SyncedFilesState = Arc::new(Mutex::new(HashMap::new()));
+    let last_sync: LastSyncState = Arc::new(Mutex::new(HashMap::new()));
+    let processor_start: ProcessorStartState = Arc::new(tokio::sync::Notify::new());
     let http_client = create_shared_client();
     let heartbeat_state: HeartbeatState = Arc::new(tokio::sync::Mutex::new(None));
     let heartbeat_status_state: HeartbeatStatusState =
@@ -427,8 +1315,15 @@ pub fn run() {
             status: None,
             is_loading: false,
             error: None,
+            failure_kind: None,
+            consecutive_failures: 0,
+            paused: false,
+            successes_total: 0,
+            failures_total: 0,
+            last_success_at: None,
         }));
     let heartbeat_task_state: HeartbeatTaskState = Arc::new(tokio::sync::Mutex::new(None));
+    let file_change_buffer: FileChangeBufferState = Arc::new(Mutex::new(Vec::new()));
 
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_log::Builder::new().build())
@@ -441,53 +1336,105 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .manage(QuitFlag(AtomicBool::new(false)))
         .manage(watcher_state)
+        .manage(file_change_buffer)
         .manage(http_client.clone())
         .manage(upload_queue.clone())
         .manage(upload_config.clone())
         .manage(upload_progress.clone())
+        .manage(upload_paused.clone())
+        .manage(in_flight_uploads.clone())
+        .manage(gitignore_cache)
+        .manage(device_info_state)
+        .manage(failed_uploads)
+        .manage(file_statuses)
+        .manage(activity_log)
+        .manage(resumable_sessions)
+        .manage(pending_deletes)
+        .manage(folder_config_overrides)
+        .manage(upload_channels)
         .manage(session_context.clone())
+        .manage(synced_files.clone())
+        .manage(processor_start.clone())
+        .manage(last_sync.clone())
         .manage(heartbeat_state.clone())
         .manage(heartbeat_status_state.clone())
         .manage(heartbeat_task_state.clone())
         .invoke_handler(tauri::generate_handler![
             start_watching,
             stop_watching,
+            stop_watching_folder,
+            get_watched_folders,
+            rescan_folder,
             get_device_info,
+            get_device_id,
+            get_device_fingerprint,
+            refresh_device_info,
             get_upload_config,
             set_upload_config,
             get_upload_progress,
+            get_last_sync_times,
+            start_processing,
+            reset_progress_counters,
             clear_upload_queue,
+            cancel_upload,
+            get_all_file_statuses,
+            get_recent_events,
+            get_failed_uploads,
+            retry_failed_uploads,
+            pause_uploads,
+            resume_uploads,
             get_queue_size,
+            get_queued_counts_by_folder,
             trigger_manual_upload,
+            force_upload,
+            trigger_manual_upload_batch,
+            preview_remote_key,
             start_heartbeat_service,
             stop_heartbeat_service,
+            pause_heartbeat,
+            resume_heartbeat,
             get_heartbeat_status_command,
             update_heartbeat_token,
+            send_heartbeat_now,
+            update_server_url,
+            export_config,
+            import_config,
             get_session_context,
             set_session_context,
             clear_session_context,
             get_org_members,
-            run_network_diagnostics
+            test_connection,
+            run_network_diagnostics,
+            get_metrics_text
         ])
         .setup(move |app| {
             // Restore session context from store
             let restored_ctx = restore_session_context(app.handle());
             *session_context.lock() = restored_ctx;
+            *last_sync.lock() = restore_last_sync_times(app.handle());
 
             // Start the upload processor in the background
             let upload_queue_clone = upload_queue.clone();
             let upload_config_clone = upload_config.clone();
             let upload_progress_clone = upload_progress.clone();
+            let upload_paused_clone = upload_paused.clone();
+            let in_flight_uploads_clone = in_flight_uploads.clone();
             let session_context_clone = session_context.clone();
+            let synced_files_clone = synced_files.clone();
             let http_client_clone = http_client.clone();
+            let processor_start_clone = processor_start.clone();
             let app_handle = app.handle().clone();
 
             tauri::async_runtime::spawn(async move {
+                wait_for_processor_start(&processor_start_clone, &upload_config_clone).await;
                 process_upload_queue(
                     upload_queue_clone,
                     upload_config_clone,
                     upload_progress_clone,
+                    upload_paused_clone,
+                    in_flight_uploads_clone,
                     session_context_clone,
+                    synced_files_clone,
                     http_client_clone,
                     app_handle,
                 )
@@ -0,0 +1,120 @@
+use std::fmt::{Display, Write};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::heartbeat::HeartbeatStatusState;
+use crate::upload::{UploadProgressState, UploadQueue};
+use crate::{cached_device_info, DeviceInfoState};
+
+const METRIC_PREFIX: &str = "labric_sync";
+
+/// Escape a label value as the exposition format requires.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    labels: &str,
+    value: impl Display,
+) {
+    let _ = writeln!(out, "# HELP {METRIC_PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {METRIC_PREFIX}_{name} {kind}");
+    let _ = writeln!(out, "{METRIC_PREFIX}_{name}{{{labels}}} {value}");
+}
+
+/// Upload and heartbeat metrics in the Prometheus text exposition format, for a sidecar to
+/// poll and serve. Upload counters restart from zero after `reset_progress_counters`.
+#[tauri::command]
+pub async fn get_metrics_text(
+    upload_progress: tauri::State<'_, UploadProgressState>,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    heartbeat_status_state: tauri::State<'_, HeartbeatStatusState>,
+    device_info_state: tauri::State<'_, DeviceInfoState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    let device_info = cached_device_info(&app_handle, device_info_state.inner())?;
+    let labels = format!(
+        "device_fingerprint=\"{}\"",
+        escape_label(&device_info.device_fingerprint)
+    );
+    let (uploaded, failed, bytes_uploaded) = {
+        let progress = upload_progress.lock();
+        (
+            progress.total_uploaded,
+            progress.total_failed,
+            progress.bytes_uploaded_total,
+        )
+    };
+    let queue_depth = upload_queue.lock().len();
+    let heartbeat = heartbeat_status_state.lock().await.clone();
+
+    let mut out = String::new();
+    write_metric(
+        &mut out,
+        "files_uploaded_total",
+        "counter",
+        "Files uploaded.",
+        &labels,
+        uploaded,
+    );
+    write_metric(
+        &mut out,
+        "files_failed_total",
+        "counter",
+        "Files that permanently failed to upload.",
+        &labels,
+        failed,
+    );
+    write_metric(
+        &mut out,
+        "queue_depth",
+        "gauge",
+        "Files waiting in the upload queue.",
+        &labels,
+        queue_depth,
+    );
+    write_metric(
+        &mut out,
+        "bytes_uploaded_total",
+        "counter",
+        "Bytes sent to storage.",
+        &labels,
+        bytes_uploaded,
+    );
+    write_metric(
+        &mut out,
+        "heartbeats_succeeded_total",
+        "counter",
+        "Successful heartbeats.",
+        &labels,
+        heartbeat.successes_total,
+    );
+    write_metric(
+        &mut out,
+        "heartbeats_failed_total",
+        "counter",
+        "Failed heartbeats.",
+        &labels,
+        heartbeat.failures_total,
+    );
+    // Left out until a heartbeat has succeeded, rather than reporting a made-up age
+    if let Some(last_success_at) = heartbeat.last_success_at {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        write_metric(
+            &mut out,
+            "last_heartbeat_age_seconds",
+            "gauge",
+            "Seconds since the last successful heartbeat.",
+            &labels,
+            now.saturating_sub(last_success_at) as f64 / 1000.0,
+        );
+    }
+    Ok(out)
+}
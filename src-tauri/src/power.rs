@@ -0,0 +1,48 @@
+use log::debug;
+use starship_battery::units::ratio::percent;
+use starship_battery::{Manager, State};
+
+/// Battery charge and power source. Both are None on machines without a battery, or where
+/// the platform doesn't report them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PowerState {
+    pub battery_percent: Option<f32>,
+    pub on_ac_power: Option<bool>,
+}
+
+pub fn read_power_state() -> PowerState {
+    let batteries: Vec<_> = match Manager::new().and_then(|manager| manager.batteries()) {
+        Ok(batteries) => batteries.flatten().collect(),
+        Err(e) => {
+            debug!("Battery information unavailable: {e}");
+            return PowerState::default();
+        }
+    };
+    if batteries.is_empty() {
+        return PowerState::default();
+    }
+
+    let battery_percent = batteries
+        .iter()
+        .map(|battery| battery.state_of_charge().get::<percent>())
+        .sum::<f32>()
+        / batteries.len() as f32;
+
+    // Any battery discharging means we're running on it; charging or full implies AC
+    let states: Vec<State> = batteries.iter().map(|battery| battery.state()).collect();
+    let on_ac_power = if states.contains(&State::Discharging) {
+        Some(false)
+    } else if states
+        .iter()
+        .any(|state| matches!(state, State::Charging | State::Full))
+    {
+        Some(true)
+    } else {
+        None
+    };
+
+    PowerState {
+        battery_percent: Some(battery_percent),
+        on_ac_power,
+    }
+}
@@ -1,42 +1,109 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
 use base64::{engine::general_purpose, Engine as _};
 use bytes::Bytes;
-use chrono::{DateTime, Utc};
-use crc32c::crc32c;
+use chrono::{DateTime, Local, TimeDelta, Timelike, Utc};
+use crc32c::{crc32c, crc32c_append};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::{debug, error, info, warn};
+use rand::Rng;
+use reqwest::header::{
+    HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, ETAG, RANGE,
+};
+use reqwest::{Body, StatusCode};
 use serde::{Deserialize, Serialize};
+use md5::Md5;
+use sha2::{Digest, Sha256};
 use parking_lot::Mutex;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_store::StoreExt;
-use tokio::sync::Semaphore;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::{Notify, Semaphore};
 use tokio::time::sleep;
-
-use futures::stream::{self, StreamExt};
-
-use crate::http_client::{check_response, SharedHttpClient};
-use crate::{EVENT_TYPE_INITIAL, EVENT_TYPE_MODIFIED};
+use tokio_util::io::ReaderStream;
+
+use futures::future::{AbortHandle, AbortRegistration, Abortable};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use futures::Stream;
+
+use crate::activity::{record_activity, LEVEL_ERROR, LEVEL_WARN};
+use crate::audit::{
+    write_audit_record, AuditRecord, AUDIT_EVENT_FAILED, AUDIT_EVENT_RETRYING, AUDIT_EVENT_UPLOADED,
+};
+use crate::auth::send_with_token_refresh;
+use crate::error::AppError;
+use crate::gitignore::{is_gitignored, is_syncignored, GitignoreCacheState};
+use crate::http_client::{check_response, header_map, SharedHttpClient};
+use crate::power::read_power_state;
+use crate::webhook::{send_webhook, SyncCompletePayload, UploadFailedPayload};
+use crate::{
+    EVENT_TYPE_CREATED, EVENT_TYPE_FORCED, EVENT_TYPE_INITIAL, EVENT_TYPE_MANUAL,
+    EVENT_TYPE_MODIFIED,
+};
 
 // Upload processing constants
-const MAX_BATCH_SIZE: usize = 1000;
+const DEFAULT_MAX_BATCH_SIZE: usize = 1000;
 const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(200);
-const MAX_RETRY_COUNT: u32 = 3;
-const RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_METADATA_BATCH_SIZE: usize = 500;
+const METADATA_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_MAX_RETRY_COUNT: u32 = 3;
+const DEFAULT_RETRY_DELAY_SECS: u64 = 5;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+/// Upper bound of the random jitter added to a retry delay, as a fraction of that delay.
+const RETRY_JITTER_FRACTION: f64 = 0.2;
+/// Times a file is sent back for a fresh presigned URL after one expires before further
+/// expiries count as failed attempts, so a clock or server problem can't loop forever.
+const MAX_EXPIRY_REQUEUES: u32 = 3;
 const DEFAULT_UPLOAD_DELAY_MS: u64 = 2000;
 const DEFAULT_MAX_CONCURRENT_UPLOADS: usize = 5;
+const DEFAULT_MAX_CONCURRENT_METADATA_UPDATES: usize = 5;
+const DEFAULT_MAX_IN_FLIGHT_BATCHES: usize = 1;
+const DEFAULT_STARTUP_DELAY_MS: u64 = 3000;
+const DEFAULT_DELETE_AFTER_UPLOAD_DELAY_SECS: u64 = 60;
+const DEFAULT_MAX_WATCHED_FOLDERS: usize = 10;
+const DEFAULT_MAX_DEBOUNCE_MS: u64 = 60_000;
+const DEFAULT_FILE_CHANGE_BATCH_MS: u64 = 100;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_UPLOAD_TIMEOUT_SECS: u64 = 1800;
+const DEFAULT_MAX_UPLOAD_MEMORY_BYTES: u64 = 256 * 1024 * 1024;
+const HASH_READ_CHUNK_SIZE: usize = 1024 * 1024;
 const BATCH_PROCESSING_DELAY: Duration = Duration::from_millis(100);
 const DISABLED_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+const PAUSED_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const UPLOAD_BYTES_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+const UPLOAD_BODY_CHUNK_SIZE: usize = 256 * 1024;
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(30);
+const MULTIPART_PART_SIZE: u64 = 16 * 1024 * 1024;
+const DEFAULT_PARALLEL_PARTS: usize = 4;
+const DEFAULT_RENAME_COALESCE_MS: u64 = 500;
+const MAX_MULTIPART_PARTS: u64 = 10_000;
+const MULTIPART_PART_ATTEMPTS: u32 = 3;
 
 // File status constants
 const STATUS_EXISTS: &str = "exists";
 const STATUS_NEEDS_UPLOAD: &str = "needs_upload";
-const STATUS_IGNORED: &str = "ignored";
+pub const STATUS_IGNORED: &str = "ignored";
 pub const STATUS_DIRECTORY: &str = "directory";
 const STATUS_QUEUED: &str = "queued";
 const STATUS_UPLOADING: &str = "uploading";
 const STATUS_UPLOADED: &str = "uploaded";
 const STATUS_FAILED: &str = "failed";
+const STATUS_KEY_TOO_LONG: &str = "key_too_long";
+const STATUS_CANCELLED: &str = "cancelled";
+const STATUS_DRY_RUN: &str = "dry_run";
+const STATUS_QUEUE_FULL: &str = "queue_full";
+const STATUS_EXPIRED: &str = "expired";
+const STATUS_DELETED: &str = "deleted";
 
 // Store filename constant
 const SETTINGS_STORE_FILENAME: &str = "settings.json";
@@ -44,13 +111,182 @@ const SETTINGS_STORE_FILENAME: &str = "settings.json";
 // ── Data types ──────────────────────────────────────────────────────────
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UploadConfig {
     pub enabled: bool,
     pub server_url: String,
+    /// Glob patterns for files never to upload, relative to the watched folder. Unlike a
+    /// plain glob, a pattern without a `/` matches the file name in any folder, a pattern with
+    /// one also matches under any subfolder, and only a leading `/` anchors it to the root.
     pub ignored_patterns: Vec<String>,
     pub upload_delay_ms: u64,
     pub max_concurrent_uploads: usize,
+    /// Limit on concurrent metadata confirmations, independent of `max_concurrent_uploads`
+    /// so a slow metadata endpoint doesn't throttle byte transfer.
+    pub max_concurrent_metadata_updates: usize,
+    /// How many batches may be read and presigned at once, so the next batch's presigned URLs
+    /// are ready while the current batch uploads. Each batch can hold up to
+    /// `max_upload_memory_bytes` of file content.
+    pub max_in_flight_batches: usize,
+    /// Most files in one presigned batch request, to match the server's limit.
+    pub max_batch_size: usize,
     pub ignore_existing_files: bool,
+    /// Upper bound on file bytes held in memory for a single batch. Batches are cut
+    /// short once this is reached, and any single file larger than this is hashed in
+    /// chunks and re-read at upload time instead of being kept in memory.
+    pub max_upload_memory_bytes: u64,
+    /// Skip `modified` events for files whose size and mtime match their last successful sync.
+    /// Off by default, since an edit that keeps the size within the mtime granularity would
+    /// be missed.
+    pub skip_unchanged_modifications: bool,
+    /// Delete local files once they are confirmed on the server. Files that changed since
+    /// upload, or that match `delete_protected_patterns`, are never deleted.
+    pub delete_after_upload: bool,
+    pub delete_after_upload_delay_secs: u64,
+    /// Matched like `ignored_patterns`.
+    pub delete_protected_patterns: Vec<String>,
+    /// How long the processor waits after launch before dispatching, unless the frontend
+    /// calls `start_processing` first. Files can still be queued during this time.
+    pub startup_delay_ms: u64,
+    /// Upload hard-linked files once. Links in a batch are read and hashed once, and their
+    /// content is uploaded once as with `dedupe_hardlinks`. Files without other links
+    /// (copy-on-write clones, and every file on Windows, whose file IDs the standard library
+    /// doesn't expose) fall back to deduping by CRC32C and size like `dedupe_by_content`.
+    pub detect_hard_links: bool,
+    /// Upload the content behind a device + inode only once; other hard links to it skip the
+    /// PUT and are just registered under their own names. Per-session and best-effort: the
+    /// record is lost on restart, and a link is only skipped while its CRC32C still matches
+    /// what was uploaded, so files without one are always uploaded. Unix only: Windows file
+    /// IDs aren't exposed by the standard library, so nothing is skipped there.
+    pub dedupe_hardlinks: bool,
+    /// Longest remote key (in bytes) the server accepts. Longer keys are handled according
+    /// to `key_truncation` before any upload is attempted.
+    pub max_key_length: Option<usize>,
+    pub key_truncation: KeyTruncation,
+    /// Before uploading a file that has to be re-read from disk, check that its content still
+    /// hashes to the CRC32C the presigned URL was issued for, and re-queue it if not.
+    pub verify_before_upload: bool,
+    /// After a single-PUT upload, check the CRC32C the storage backend reports for the stored
+    /// object, from the PUT response's `x-goog-hash` or a HEAD of the batch response's
+    /// `verify_url`. A mismatch fails the upload so it is retried.
+    pub verify_after_upload: bool,
+    /// Notified when the queue drains after a round of uploads.
+    pub completion_webhook_url: Option<String>,
+    /// Notified when a file permanently fails to upload.
+    pub failure_webhook_url: Option<String>,
+    /// Always ask the server about every file found by the initial scan. When false, files
+    /// unchanged since their last sync this session are skipped just like live events.
+    pub initial_scan_always_verify: bool,
+    /// Upload attempts per file before it is marked as permanently failed.
+    pub max_retry_count: u32,
+    /// Base delay before retrying, doubled on each further attempt.
+    pub retry_delay_secs: u64,
+    /// Order in which ready files are taken from the queue for each batch.
+    pub upload_order: UploadOrder,
+    /// Upper bound on folders watched at once, each of which holds its own OS watcher and
+    /// competes for batch slots. Lowering it doesn't stop folders already being watched.
+    pub max_watched_folders: usize,
+    /// Also skip files excluded by `.gitignore` files in the watched folder, including
+    /// nested ones in subdirectories.
+    pub respect_gitignore: bool,
+    /// Also skip files matching a `.syncignore` (gitignore syntax) in the watched folder, on
+    /// top of `ignored_patterns`. Independent of `respect_gitignore`, so sync rules can differ
+    /// from git's.
+    pub respect_syncignore: bool,
+    /// Upload a file once it has been queued this long, even if it keeps being modified and
+    /// so never sits idle for `upload_delay_ms`. None waits for the file to settle.
+    pub max_debounce_ms: Option<u64>,
+    /// How many directory levels below a watched folder to scan and watch; 0 means only the
+    /// folder's own files. Changes deeper than this are not detected at all. Directories
+    /// created after watching starts aren't watched until the folder is watched again.
+    pub max_watch_depth: Option<usize>,
+    /// Files larger than this are never uploaded.
+    pub max_file_size_bytes: Option<u64>,
+    /// Hold uploads while running on battery. Files keep queueing and upload once AC power
+    /// returns. Devices that can't report their power source upload as normal.
+    pub upload_only_on_ac: bool,
+    /// Daily windows, in local time, during which uploads may run. Outside them files keep
+    /// queueing and upload once the next window opens. Empty means uploads run at any time.
+    pub upload_windows: Vec<UploadWindow>,
+    /// Checksum header sent with each PUT so the storage backend rejects a corrupted body.
+    /// Off by default: the presigned URL's signature must allow the header.
+    pub integrity_header: IntegrityHeader,
+    /// Timeout for API calls to the sync server.
+    pub request_timeout_secs: u64,
+    /// Timeout for a whole file PUT. Timed-out uploads are retried like any other failure.
+    pub upload_timeout_secs: u64,
+    /// Go through queueing, ignore rules and hashing as normal, but never contact the server.
+    /// Files that would be uploaded get a `dry_run` status and count as uploaded.
+    pub dry_run: bool,
+    /// When non-empty, only files matching one of these are uploaded (if not also ignored).
+    /// Matched like `ignored_patterns`.
+    pub include_patterns: Vec<String>,
+    /// Skip the PUT for content already uploaded this session, identified by CRC32C and size,
+    /// and only confirm the new file name. For backends that dedupe by content hash. A CRC32C
+    /// collision between same-sized files would register the wrong content, a tiny risk
+    /// traded for bandwidth.
+    pub dedupe_by_content: bool,
+    /// Files larger than this are uploaded in parts, each retried on its own, so a dropped
+    /// connection doesn't restart the whole file. No integrity header is sent with parts.
+    /// Falls back to a single PUT if the server doesn't support multipart uploads.
+    pub multipart_threshold_bytes: Option<u64>,
+    /// How many parts of one multipart upload are sent at once, each read from its own
+    /// offset of the file. Also bounded by `max_concurrent_uploads`.
+    pub parallel_parts: usize,
+    /// Upload through a resumable session so a retry continues from the bytes the storage
+    /// backend already received instead of starting over. Needs server support; falls back to
+    /// a single PUT without it. No integrity header is sent with resumable uploads.
+    pub resumable_uploads: bool,
+    /// Gzip file bodies (except already-compressed types, and files over
+    /// `max_upload_memory_bytes`) and send them with `Content-Encoding: gzip`. The CRC32C is of
+    /// the compressed bytes. The server must store and serve them with the encoding intact.
+    pub compress_uploads: bool,
+    /// Base64-encoded 256-bit key. When set, file bodies are encrypted on this device with
+    /// AES-256-GCM (random nonce prepended to the ciphertext), so the server only ever stores
+    /// opaque blobs. The nonce makes every attempt's ciphertext different, so the batch request
+    /// carries the plaintext's CRC32C to keep unchanged files recognised as already uploaded;
+    /// the integrity header and `verify_after_upload` use the ciphertext's. The key never
+    /// leaves the device: keeping it safe, and the same across devices that must read the
+    /// files, is up to the user. A lost key makes uploaded files unrecoverable. Files over
+    /// `max_upload_memory_bytes` can't be encrypted and are not uploaded.
+    pub encryption_key: Option<String>,
+    /// Extra headers (API keys, tenant IDs, tracing) sent with every request to the sync
+    /// server. Never sent to presigned storage URLs, whose requests are signed.
+    pub extra_headers: HashMap<String, String>,
+    /// Content types by lowercase file extension (without the dot), used instead of the
+    /// guessed type for extensions that are missing or wrong in the built-in table.
+    pub content_type_overrides: HashMap<String, String>,
+    /// Extra headers sent with heartbeat requests.
+    pub heartbeat_extra_headers: HashMap<String, String>,
+    /// Most files waiting in the queue, so a flood of new files can't exhaust memory. None
+    /// (the default) is unbounded. Files already uploading and coming back for a retry don't
+    /// count against the cap when they're re-queued.
+    pub max_queue_size: Option<usize>,
+    /// What happens to a new file when the queue is full. Either way a `queue_full` event is
+    /// emitted and the file left out gets a `queue_full` status.
+    pub queue_full_policy: QueueFullPolicy,
+    /// Discard queued files that were first queued longer ago than this, assuming they're
+    /// stale, with an `expired` status. None (the default) keeps them indefinitely.
+    pub max_item_age_secs: Option<u64>,
+    /// Delete files from the server when they're deleted locally. Deletes wait out
+    /// `upload_delay_ms` like uploads, and are dropped if the file reappears meanwhile, as it
+    /// does when an editor saves by deleting and recreating it. Off by default.
+    pub sync_deletes: bool,
+    /// Renames seen within this window are handled together, so a renamed directory becomes
+    /// one directory move on the server instead of a move per file. Falls back to per-file
+    /// moves if the renames don't share a directory or the server can't move directories.
+    pub rename_coalesce_ms: u64,
+    /// File changes seen within this window are sent to the frontend together as one
+    /// `file_changes_batch` event. 0 sends each change in its own batch.
+    pub file_change_batch_ms: u64,
+    /// Also emit the older per-file `file_change` event for every change.
+    pub emit_file_change_events: bool,
+    /// Write a JSON line per upload success, retry and failure to `sync-audit.jsonl` in the
+    /// app data dir, for collecting centrally. Rotated by size.
+    pub audit_log: bool,
+    /// Follow symlinks while scanning. When off, symlinks found by a scan are skipped and
+    /// files that resolve outside the watched folder through a symlink aren't uploaded.
+    pub follow_symlinks: bool,
 }
 
 impl Default for UploadConfig {
@@ -68,26 +304,527 @@ impl Default for UploadConfig {
             ],
             upload_delay_ms: DEFAULT_UPLOAD_DELAY_MS,
             max_concurrent_uploads: DEFAULT_MAX_CONCURRENT_UPLOADS,
+            max_concurrent_metadata_updates: DEFAULT_MAX_CONCURRENT_METADATA_UPDATES,
+            max_in_flight_batches: DEFAULT_MAX_IN_FLIGHT_BATCHES,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
             ignore_existing_files: false,
+            max_upload_memory_bytes: DEFAULT_MAX_UPLOAD_MEMORY_BYTES,
+            skip_unchanged_modifications: false,
+            delete_after_upload: false,
+            delete_after_upload_delay_secs: DEFAULT_DELETE_AFTER_UPLOAD_DELAY_SECS,
+            delete_protected_patterns: vec![],
+            startup_delay_ms: DEFAULT_STARTUP_DELAY_MS,
+            detect_hard_links: false,
+            dedupe_hardlinks: false,
+            max_key_length: None,
+            key_truncation: KeyTruncation::default(),
+            verify_before_upload: false,
+            verify_after_upload: false,
+            completion_webhook_url: None,
+            failure_webhook_url: None,
+            initial_scan_always_verify: true,
+            max_retry_count: DEFAULT_MAX_RETRY_COUNT,
+            retry_delay_secs: DEFAULT_RETRY_DELAY_SECS,
+            upload_order: UploadOrder::default(),
+            max_watched_folders: DEFAULT_MAX_WATCHED_FOLDERS,
+            respect_gitignore: false,
+            respect_syncignore: false,
+            max_debounce_ms: Some(DEFAULT_MAX_DEBOUNCE_MS),
+            max_watch_depth: None,
+            max_file_size_bytes: None,
+            upload_only_on_ac: false,
+            upload_windows: vec![],
+            integrity_header: IntegrityHeader::default(),
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            upload_timeout_secs: DEFAULT_UPLOAD_TIMEOUT_SECS,
+            dry_run: false,
+            include_patterns: vec![],
+            dedupe_by_content: false,
+            multipart_threshold_bytes: None,
+            parallel_parts: DEFAULT_PARALLEL_PARTS,
+            resumable_uploads: false,
+            compress_uploads: false,
+            encryption_key: None,
+            extra_headers: HashMap::new(),
+            content_type_overrides: HashMap::new(),
+            heartbeat_extra_headers: HashMap::new(),
+            max_queue_size: None,
+            queue_full_policy: QueueFullPolicy::default(),
+            max_item_age_secs: None,
+            sync_deletes: false,
+            rename_coalesce_ms: DEFAULT_RENAME_COALESCE_MS,
+            file_change_batch_ms: DEFAULT_FILE_CHANGE_BATCH_MS,
+            emit_file_change_events: false,
+            audit_log: false,
+            follow_symlinks: false,
+        }
+    }
+}
+
+impl UploadConfig {
+    /// Timeout for API calls to the sync server.
+    fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    /// Headers for requests to the sync server: `extra_headers`, already checked by `validate`.
+    fn server_headers(&self) -> HeaderMap {
+        header_map(&self.extra_headers).unwrap_or_else(|e| {
+            warn!("Not sending extra headers: {e}");
+            HeaderMap::new()
+        })
+    }
+
+    /// Take the settings that can hold credentials from `other`: the encryption key, extra
+    /// headers and webhook URLs, which often embed tokens.
+    pub fn copy_secrets_from(&mut self, other: &UploadConfig) {
+        self.encryption_key = other.encryption_key.clone();
+        self.extra_headers = other.extra_headers.clone();
+        self.heartbeat_extra_headers = other.heartbeat_extra_headers.clone();
+        self.completion_webhook_url = other.completion_webhook_url.clone();
+        self.failure_webhook_url = other.failure_webhook_url.clone();
+    }
+
+    /// Check for values that would break uploading, listing every problem found.
+    pub fn validate(&self) -> Result<(), AppError> {
+        // (field, problem) pairs, so the frontend can point at the offending settings
+        let mut problems: Vec<(&str, String)> = Vec::new();
+
+        if self.max_concurrent_uploads == 0 {
+            problems.push((
+                "max_concurrent_uploads",
+                "max_concurrent_uploads must be at least 1".to_string(),
+            ));
+        }
+        if self.max_concurrent_metadata_updates == 0 {
+            problems.push((
+                "max_concurrent_metadata_updates",
+                "max_concurrent_metadata_updates must be at least 1".to_string(),
+            ));
+        }
+        if self.parallel_parts == 0 {
+            problems.push((
+                "parallel_parts",
+                "parallel_parts must be at least 1".to_string(),
+            ));
+        }
+        if self.max_in_flight_batches == 0 {
+            problems.push((
+                "max_in_flight_batches",
+                "max_in_flight_batches must be at least 1".to_string(),
+            ));
+        }
+        if self.max_batch_size == 0 {
+            problems.push((
+                "max_batch_size",
+                "max_batch_size must be at least 1".to_string(),
+            ));
+        }
+        if self.max_watched_folders == 0 {
+            problems.push((
+                "max_watched_folders",
+                "max_watched_folders must be at least 1".to_string(),
+            ));
+        }
+        if self.request_timeout_secs == 0 {
+            problems.push((
+                "request_timeout_secs",
+                "request_timeout_secs must be at least 1 second".to_string(),
+            ));
+        }
+        if self.upload_timeout_secs == 0 {
+            problems.push((
+                "upload_timeout_secs",
+                "upload_timeout_secs must be at least 1 second".to_string(),
+            ));
+        }
+        match reqwest::Url::parse(&self.server_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => problems.push((
+                "server_url",
+                format!("server_url must use http or https, not '{}'", url.scheme()),
+            )),
+            Err(e) => problems.push((
+                "server_url",
+                format!("server_url '{}' is invalid: {e}", self.server_url),
+            )),
+        }
+        for pattern in &self.ignored_patterns {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                problems.push((
+                    "ignored_patterns",
+                    format!("ignored_patterns entry '{pattern}' is invalid: {e}"),
+                ));
+            }
+        }
+        for pattern in &self.include_patterns {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                problems.push((
+                    "include_patterns",
+                    format!("include_patterns entry '{pattern}' is invalid: {e}"),
+                ));
+            }
+        }
+        if let Some(Err(e)) = self.encryption_key.as_deref().map(encryption_cipher) {
+            problems.push(("encryption_key", e));
+        }
+        if let Err(e) = header_map(&self.extra_headers) {
+            problems.push(("extra_headers", format!("extra_headers: {e}")));
+        }
+        if let Err(e) = header_map(&self.heartbeat_extra_headers) {
+            problems.push((
+                "heartbeat_extra_headers",
+                format!("heartbeat_extra_headers: {e}"),
+            ));
+        }
+        for window in &self.upload_windows {
+            if window.bounds().is_none() {
+                problems.push((
+                    "upload_windows",
+                    format!(
+                        "upload_windows entry '{}-{}' must use HH:MM times",
+                        window.start, window.end
+                    ),
+                ));
+            }
+        }
+        for (extension, content_type) in &self.content_type_overrides {
+            if HeaderValue::from_str(content_type).is_err() {
+                problems.push((
+                    "content_type_overrides",
+                    format!("content_type_overrides entry for '{extension}' is not a valid content type"),
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let message = problems
+            .iter()
+            .map(|(_, problem)| problem.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let mut fields: Vec<String> = problems
+            .iter()
+            .map(|(field, _)| field.to_string())
+            .collect();
+        fields.dedup();
+        Err(AppError::Config {
+            fields,
+            message: format!("Invalid upload configuration: {message}"),
+        })
+    }
+}
+
+/// Settings a watched folder can set for itself, e.g. to sync only new files in one folder
+/// while fully syncing another. They decide which files under the folder get queued and how
+/// it's scanned; unset fields fall back to the global config.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct UploadConfigOverride {
+    pub ignore_existing_files: Option<bool>,
+    pub ignored_patterns: Option<Vec<String>>,
+    pub include_patterns: Option<Vec<String>>,
+    pub respect_gitignore: Option<bool>,
+    pub respect_syncignore: Option<bool>,
+    pub skip_unchanged_modifications: Option<bool>,
+    pub max_file_size_bytes: Option<u64>,
+    pub max_watch_depth: Option<usize>,
+    pub follow_symlinks: Option<bool>,
+}
+
+impl UploadConfigOverride {
+    fn apply(&self, config: &mut UploadConfig) {
+        if let Some(ignore_existing_files) = self.ignore_existing_files {
+            config.ignore_existing_files = ignore_existing_files;
+        }
+        if let Some(ignored_patterns) = &self.ignored_patterns {
+            config.ignored_patterns = ignored_patterns.clone();
+        }
+        if let Some(include_patterns) = &self.include_patterns {
+            config.include_patterns = include_patterns.clone();
+        }
+        if let Some(respect_gitignore) = self.respect_gitignore {
+            config.respect_gitignore = respect_gitignore;
+        }
+        if let Some(respect_syncignore) = self.respect_syncignore {
+            config.respect_syncignore = respect_syncignore;
+        }
+        if let Some(skip_unchanged_modifications) = self.skip_unchanged_modifications {
+            config.skip_unchanged_modifications = skip_unchanged_modifications;
+        }
+        if let Some(max_file_size_bytes) = self.max_file_size_bytes {
+            config.max_file_size_bytes = Some(max_file_size_bytes);
+        }
+        if let Some(max_watch_depth) = self.max_watch_depth {
+            config.max_watch_depth = Some(max_watch_depth);
+        }
+        if let Some(follow_symlinks) = self.follow_symlinks {
+            config.follow_symlinks = follow_symlinks;
+        }
+    }
+}
+
+/// Config for files under the watched folder `base_path`: the global config with the
+/// folder's override, if any, applied.
+pub fn effective_upload_config(
+    base_path: &str,
+    upload_config: &UploadConfigState,
+    app_handle: &AppHandle,
+) -> UploadConfig {
+    let mut config = upload_config.lock().clone();
+    if let Some(overrides) = app_handle.try_state::<FolderConfigOverridesState>() {
+        if let Some(config_override) = overrides.lock().get(base_path) {
+            config_override.apply(&mut config);
+        }
+    }
+    config
+}
+
+/// Set or clear a watched folder's config override, checking the config it results in.
+pub fn set_folder_config_override(
+    folder_path: &str,
+    config_override: Option<UploadConfigOverride>,
+    overrides: &FolderConfigOverridesState,
+    upload_config: &UploadConfigState,
+) -> Result<(), AppError> {
+    let Some(config_override) = config_override else {
+        overrides.lock().remove(folder_path);
+        return Ok(());
+    };
+    let mut config = upload_config.lock().clone();
+    config_override.apply(&mut config);
+    config.validate()?;
+    overrides
+        .lock()
+        .insert(folder_path.to_string(), config_override);
+    Ok(())
+}
+
+/// A daily upload window in local time. `start` and `end` are "HH:MM"; a window ending
+/// before it starts runs past midnight, and equal times cover the whole day.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct UploadWindow {
+    pub start: String,
+    pub end: String,
+}
+
+impl UploadWindow {
+    /// Start and end as minutes past midnight.
+    fn bounds(&self) -> Option<(u32, u32)> {
+        Some((
+            parse_window_time(&self.start)?,
+            parse_window_time(&self.end)?,
+        ))
+    }
+
+    fn contains(&self, minute: u32) -> bool {
+        match self.bounds() {
+            Some((start, end)) if start < end => (start..end).contains(&minute),
+            Some((start, end)) if start > end => minute >= start || minute < end,
+            Some(_) => true,
+            None => false,
         }
     }
 }
 
+fn parse_window_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// When the next upload window opens, or None if uploads may run now.
+fn next_upload_window(windows: &[UploadWindow], now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if windows.is_empty() {
+        return None;
+    }
+    let minute = now.hour() * 60 + now.minute();
+    if windows.iter().any(|window| window.contains(minute)) {
+        return None;
+    }
+    let minutes_until = windows
+        .iter()
+        .filter_map(UploadWindow::bounds)
+        .map(|(start, _)| (start + 24 * 60 - minute) % (24 * 60))
+        .min()?;
+    let minute_start = now.with_second(0)?.with_nanosecond(0)?;
+    Some(minute_start + TimeDelta::minutes(minutes_until.into()))
+}
+
+/// What to do with files whose remote key exceeds `max_key_length`.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyTruncation {
+    /// Don't upload the file; report it with a `key_too_long` status.
+    #[default]
+    Skip,
+    /// Replace the overlong tail with a stable hash of the full key, keeping the extension.
+    Hash,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityHeader {
+    /// No header, so PUTs match URLs whose signature doesn't cover one.
+    #[default]
+    None,
+    /// `x-goog-hash: crc32c=...`, reusing the CRC32C sent in the batch request.
+    GoogHash,
+    /// S3-style `Content-MD5`. Costs an extra read for files streamed from disk.
+    ContentMd5,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadOrder {
+    /// Oldest queued first.
+    #[default]
+    Fifo,
+    /// Smallest files first, so a huge file doesn't hold up many small ones.
+    SmallestFirst,
+    LargestFirst,
+}
+
+/// What to do with a new file when the queue already holds `max_queue_size` files.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueFullPolicy {
+    /// Don't queue the new file.
+    #[default]
+    RejectNew,
+    /// Drop the longest-queued file to make room. Nothing has started uploading it yet.
+    DropOldest,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct UploadItem {
     pub path: String,
     pub relative_path: String,
+    /// Watched root the file was queued from.
+    pub base_path: String,
+    /// Key the file is stored under on the server. Usually equal to `relative_path`.
+    pub remote_key: String,
+    /// Epoch millis of the latest change, reset each time the file is re-queued.
     pub timestamp: u64,
+    /// Epoch millis the file was first queued, kept across re-queues.
+    pub first_seen: u64,
     pub retry_count: u32,
+    /// Size at enqueue time, used to budget batch memory.
+    pub file_size: u64,
+    /// Epoch millis before which the item must not be retried (0 if not backing off).
+    pub not_before: u64,
+    /// Times the item was re-queued because its presigned URL expired.
+    #[serde(default)]
+    pub expiry_requeues: u32,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, Default)]
 pub struct UploadProgress {
     pub total_queued: usize,
     pub total_uploaded: usize,
     pub total_failed: usize,
     pub in_flight: usize,
     pub current_uploading: Option<String>,
+    /// Uploads are held back because `upload_only_on_ac` is set and the device is on battery.
+    pub waiting_for_power: bool,
+    /// Epoch millis the next `upload_windows` window opens, while uploads are held outside
+    /// the schedule.
+    pub schedule_resumes_at: Option<u64>,
+    /// Bytes sent to storage; files the server already had don't count.
+    pub bytes_uploaded_total: u64,
+    /// Average over the last `THROUGHPUT_WINDOW`.
+    pub current_bytes_per_sec: f64,
+    /// Best-effort guess at how long the queued files will take at the current rate, based on
+    /// their sizes when queued. None until something has been uploaded recently.
+    pub estimated_seconds_remaining: Option<u64>,
+    /// Mean time a file took to upload over the last `THROUGHPUT_WINDOW`, or for the latest
+    /// uploads if nothing finished since. None until something has been uploaded.
+    pub average_upload_ms: Option<u64>,
+    /// Completion time, size and duration of recent uploads, for the rolling averages.
+    #[serde(skip)]
+    recent_uploads: VecDeque<(Instant, u64, Duration)>,
+}
+
+impl UploadProgress {
+    fn record_upload(&mut self, bytes: u64, queued_bytes: u64, duration: Duration) {
+        self.bytes_uploaded_total = self.bytes_uploaded_total.saturating_add(bytes);
+        self.recent_uploads
+            .push_back((Instant::now(), bytes, duration));
+        self.refresh_rates(queued_bytes);
+    }
+
+    /// Drop uploads that have left `THROUGHPUT_WINDOW` and recompute the rates, so they fall
+    /// back towards zero while nothing is completing.
+    fn refresh_rates(&mut self, queued_bytes: u64) {
+        let now = Instant::now();
+        while self
+            .recent_uploads
+            .front()
+            .is_some_and(|(at, _, _)| now.duration_since(*at) > THROUGHPUT_WINDOW)
+        {
+            self.recent_uploads.pop_front();
+        }
+
+        let window_bytes: u64 = self.recent_uploads.iter().map(|(_, bytes, _)| bytes).sum();
+        let window_duration: Duration = self.recent_uploads.iter().map(|(_, _, d)| *d).sum();
+        // An empty window keeps the last average, as documented on `average_upload_ms`
+        if !self.recent_uploads.is_empty() {
+            self.average_upload_ms =
+                Some((window_duration / self.recent_uploads.len() as u32).as_millis() as u64);
+        }
+        self.current_bytes_per_sec = window_bytes as f64 / THROUGHPUT_WINDOW.as_secs_f64();
+        self.estimated_seconds_remaining = (self.current_bytes_per_sec > 0.0)
+            .then(|| (queued_bytes as f64 / self.current_bytes_per_sec).ceil() as u64);
+    }
+}
+
+/// Payload of `file_uploaded`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct FileUploadedEvent {
+    relative_path: String,
+    /// How long the upload took. None when the server already had the file.
+    duration_ms: Option<u64>,
+}
+
+/// How a local file maps to its server key, as returned by `preview_remote_key`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RemoteKeyPreview {
+    pub relative_path: String,
+    /// `None` when the key exceeds `max_key_length` and the file would be skipped.
+    pub remote_key: Option<String>,
+    pub truncated: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct LastSyncUpdatedEvent {
+    folder: String,
+    timestamp: u64,
+}
+
+/// Emitted as `file_upload_bytes` while a file's content is being sent.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct FileUploadBytesEvent {
+    relative_path: String,
+    uploaded: u64,
+    total: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct QueueFullEvent {
+    /// The file left out of the queue: the new one, or the oldest with `drop_oldest`
+    relative_path: String,
+    max_queue_size: usize,
+    policy: QueueFullPolicy,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct KeyTooLongEvent {
+    relative_path: String,
+    key_length: usize,
+    max_key_length: usize,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -97,9 +834,264 @@ pub struct FileUploadStatus {
     pub error: Option<String>,
 }
 
+/// Latest status of every file the app knows about, keyed by relative path, so the UI can
+/// rebuild its view without having seen every `file_upload_status` event.
+pub type FileStatusesState = Arc<Mutex<HashMap<String, FileUploadStatus>>>;
+
 pub type UploadQueue = Arc<Mutex<VecDeque<UploadItem>>>;
 pub type UploadConfigState = Arc<Mutex<UploadConfig>>;
+/// Config overrides for watched folders, keyed by the folder's path.
+pub type FolderConfigOverridesState = Arc<Mutex<HashMap<String, UploadConfigOverride>>>;
 pub type UploadProgressState = Arc<Mutex<UploadProgress>>;
+/// While true the processor leaves the queue alone; files are still queued by the watcher.
+pub type UploadPausedState = Arc<Mutex<bool>>;
+/// Abort handles for uploads currently being PUT, keyed by relative path.
+pub type InFlightUploadsState = Arc<Mutex<HashMap<String, InFlightUpload>>>;
+
+pub struct InFlightUpload {
+    id: u64,
+    /// Absolute path of the file
+    path: String,
+    abort_handle: AbortHandle,
+}
+
+static NEXT_IN_FLIGHT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Keeps an upload registered as cancellable until dropped. Only removes its own entry, in
+/// case a newer upload of the same path has registered since.
+struct InFlightGuard {
+    registry: InFlightUploadsState,
+    relative_path: String,
+    id: u64,
+}
+
+impl InFlightGuard {
+    fn register(
+        registry: &InFlightUploadsState,
+        item: &UploadItem,
+        abort_handle: AbortHandle,
+    ) -> Self {
+        let id = NEXT_IN_FLIGHT_ID.fetch_add(1, Ordering::Relaxed);
+        registry.lock().insert(
+            item.relative_path.clone(),
+            InFlightUpload {
+                id,
+                path: item.path.clone(),
+                abort_handle,
+            },
+        );
+        Self {
+            registry: registry.clone(),
+            relative_path: item.relative_path.clone(),
+            id,
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut registry = self.registry.lock();
+        if registry
+            .get(&self.relative_path)
+            .is_some_and(|upload| upload.id == self.id)
+        {
+            registry.remove(&self.relative_path);
+        }
+    }
+}
+
+/// Signalled by the frontend once it has finished initializing (config loaded, token set).
+pub type ProcessorStartState = Arc<Notify>;
+/// Size and mtime of a file, used to tell whether it changed since it was last synced.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileFingerprint {
+    fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// Fingerprints of files as they were when last confirmed on the server, keyed by absolute path.
+pub type SyncedFilesState = Arc<Mutex<HashMap<String, FileFingerprint>>>;
+/// Epoch millis of the most recent successful sync per watched root.
+pub type LastSyncState = Arc<Mutex<HashMap<String, u64>>>;
+/// Files that ran out of retries, keyed by absolute path, until they upload successfully.
+pub type FailedUploadsState = Arc<Mutex<HashMap<String, FailedUpload>>>;
+
+/// Local deletes waiting to be synced to the server, keyed by absolute path.
+pub type PendingDeletesState = Arc<Mutex<HashMap<String, PendingDelete>>>;
+
+pub struct PendingDelete {
+    relative_path: String,
+    remote_key: String,
+    /// Epoch millis of the delete event
+    timestamp: u64,
+    retry_count: u32,
+}
+
+/// Resumable upload sessions left unfinished by a failed attempt, keyed by absolute path.
+pub type ResumableSessionsState = Arc<Mutex<HashMap<String, ResumableSession>>>;
+
+#[derive(Clone)]
+pub struct ResumableSession {
+    session_url: String,
+    remote_key: String,
+    fingerprint: Option<FileFingerprint>,
+    /// Attempts made through this session, for logging
+    attempts: u32,
+}
+
+pub struct FailedUpload {
+    item: UploadItem,
+    error: String,
+}
+
+/// A permanently failed file, as returned by `get_failed_uploads`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct FailedUploadInfo {
+    pub relative_path: String,
+    pub base_path: String,
+    pub error: String,
+}
+
+/// Longest response body kept on an `UploadError`.
+const MAX_ERROR_BODY_LEN: usize = 1024;
+
+/// What kind of failure an upload hit, so the frontend can tell them apart.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadErrorCategory {
+    /// No response: connection failure or timeout
+    Network,
+    /// 401 or 403
+    Auth,
+    /// A 403 (or GCS's 400) saying the presigned URL expired before it was used
+    UrlExpired,
+    /// 5xx, or any other unexpected status
+    Server,
+    /// Any other 4xx
+    Client,
+    /// The file couldn't be read or prepared, so nothing was sent
+    Local,
+}
+
+/// Why an upload failed. `message` is the human-readable form used for logs and statuses.
+#[derive(Clone, Serialize, Debug)]
+pub struct UploadError {
+    pub message: String,
+    pub category: UploadErrorCategory,
+    pub status_code: Option<u16>,
+    /// Response body, truncated to `MAX_ERROR_BODY_LEN`
+    pub body: Option<String>,
+}
+
+impl UploadError {
+    fn network(message: String) -> Self {
+        UploadError {
+            message,
+            category: UploadErrorCategory::Network,
+            status_code: None,
+            body: None,
+        }
+    }
+
+    /// The server answered successfully, but with something unusable: an unparseable body, say.
+    fn server(message: String) -> Self {
+        UploadError {
+            message,
+            category: UploadErrorCategory::Server,
+            status_code: None,
+            body: None,
+        }
+    }
+
+    /// Build the error for an unsuccessful response, reading its body.
+    async fn from_response(response: reqwest::Response, context: &str) -> Self {
+        let status = response.status();
+        let mut body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unable to read response".to_string());
+        if body.len() > MAX_ERROR_BODY_LEN {
+            let mut end = MAX_ERROR_BODY_LEN;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+        }
+        let category = match status {
+            StatusCode::FORBIDDEN | StatusCode::BAD_REQUEST if is_expired_url_body(&body) => {
+                UploadErrorCategory::UrlExpired
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => UploadErrorCategory::Auth,
+            status if status.is_client_error() => UploadErrorCategory::Client,
+            _ => UploadErrorCategory::Server,
+        };
+        UploadError {
+            message: format!("{context} failed with status {status}: {body}"),
+            category,
+            status_code: Some(status.as_u16()),
+            body: Some(body),
+        }
+    }
+}
+
+impl From<String> for UploadError {
+    fn from(message: String) -> Self {
+        UploadError {
+            message,
+            category: UploadErrorCategory::Local,
+            status_code: None,
+            body: None,
+        }
+    }
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Whether a storage error body says the signed URL has expired, as S3 ("Request has
+/// expired"), GCS (`ExpiredToken`) and Azure ("not valid in the specified time frame") word it.
+fn is_expired_url_body(body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    body.contains("request has expired")
+        || body.contains("expiredtoken")
+        || body.contains("not valid in the specified time frame")
+}
+
+/// Like `check_response`, keeping the status and body on the error.
+async fn check_upload_response(
+    response: reqwest::Response,
+    context: &str,
+) -> Result<reqwest::Response, UploadError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(UploadError::from_response(response, context).await)
+    }
+}
+
+/// A caller's status channel and the relative paths it's still waiting on.
+pub struct UploadChannel {
+    channel: Channel<FileUploadStatus>,
+    pending: HashSet<String>,
+}
+
+/// Per-call status channels, keyed by a unique id so callers queueing the same file don't
+/// replace each other. An entry is dropped once all its files settle or its channel closes.
+pub type UploadChannelsState = Arc<Mutex<HashMap<u64, UploadChannel>>>;
+
+static NEXT_UPLOAD_CHANNEL_ID: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct SessionContext {
@@ -149,6 +1141,10 @@ struct FileCheckItem {
     file_created_at: Option<String>,
     #[serde(rename = "fileModifiedAt", skip_serializing_if = "Option::is_none")]
     file_modified_at: Option<String>,
+    #[serde(rename = "contentEncoding", skip_serializing_if = "Option::is_none")]
+    content_encoding: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    encrypted: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -160,6 +1156,69 @@ struct GetPresignedBatchBody {
     session_metadata: Option<HashMap<String, String>>,
 }
 
+#[derive(Serialize)]
+struct MetadataBatchBody {
+    #[serde(rename = "fileIds")]
+    file_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RenameDirectoryBody {
+    #[serde(rename = "oldPrefix")]
+    old_prefix: String,
+    #[serde(rename = "newPrefix")]
+    new_prefix: String,
+}
+
+#[derive(Serialize)]
+struct RenameFileBody {
+    #[serde(rename = "oldFileName")]
+    old_file_name: String,
+    #[serde(rename = "newFileName")]
+    new_file_name: String,
+}
+
+#[derive(Serialize)]
+struct DeleteFileBody {
+    #[serde(rename = "fileName")]
+    file_name: String,
+    #[serde(rename = "deviceFingerprint")]
+    device_fingerprint: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MultipartUrlsBody {
+    #[serde(rename = "partCount")]
+    part_count: u64,
+    #[serde(rename = "partSize")]
+    part_size: u64,
+}
+
+#[derive(Deserialize)]
+struct MultipartUrlsResponse {
+    upload_id: String,
+    urls: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ResumableSessionResponse {
+    session_url: String,
+}
+
+#[derive(Serialize)]
+struct CompletedPart {
+    #[serde(rename = "partNumber")]
+    part_number: usize,
+    etag: String,
+}
+
+#[derive(Serialize)]
+struct CompleteMultipartBody {
+    #[serde(rename = "uploadId")]
+    upload_id: String,
+    parts: Vec<CompletedPart>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct FileCheckResult {
     file_name: String,
@@ -167,6 +1226,8 @@ struct FileCheckResult {
     status: String,
     file_id: String,
     upload_url: Option<String>,
+    /// Where the stored object can be HEADed to check it after upload
+    verify_url: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -177,10 +1238,17 @@ struct GetPresignedBatchResponse {
 }
 
 /// An upload item paired with its already-read file content, to avoid reading twice.
+/// `file_content` is `None` when the file was too large to keep in memory and must be
+/// read again at upload time.
 struct PreparedUpload {
     item: UploadItem,
-    file_content: Bytes,
+    file_content: Option<Bytes>,
     content_type: String,
+    /// `gzip` when `file_content` was compressed
+    content_encoding: Option<String>,
+    fingerprint: Option<FileFingerprint>,
+    /// CRC32C of the encrypted body, which differs from the plaintext's in the batch request
+    body_crc32c: Option<String>,
 }
 
 // ── Small helpers ───────────────────────────────────────────────────────
@@ -199,91 +1267,483 @@ pub fn emit_file_upload_status(
     if let Err(e) = app_handle.emit("file_upload_status", &upload_status) {
         warn!("Failed to emit file upload status event: {e}");
     }
+    if let Some(file_statuses) = app_handle.try_state::<FileStatusesState>() {
+        file_statuses
+            .lock()
+            .insert(relative_path.to_string(), upload_status.clone());
+    }
+
+    if let Some(channels) = app_handle.try_state::<UploadChannelsState>() {
+        let mut channels = channels.lock();
+        let is_final = matches!(
+            status,
+            STATUS_UPLOADED
+                | STATUS_FAILED
+                | STATUS_IGNORED
+                | STATUS_DIRECTORY
+                | STATUS_KEY_TOO_LONG
+                | STATUS_CANCELLED
+                | STATUS_DRY_RUN
+                | STATUS_QUEUE_FULL
+                | STATUS_EXPIRED
+                | STATUS_DELETED
+        );
+        channels.retain(|_, entry| {
+            if !entry.pending.contains(relative_path) {
+                return true;
+            }
+            if let Err(e) = entry.channel.send(upload_status.clone()) {
+                warn!("Failed to send file upload status over channel: {e}");
+                return false;
+            }
+            if is_final {
+                entry.pending.remove(relative_path);
+            }
+            !entry.pending.is_empty()
+        });
+    }
 }
 
-pub fn should_ignore_file(file_path: &str, ignored_patterns: &[String]) -> bool {
-    ignored_patterns.iter().any(|pattern| {
-        glob::Pattern::new(pattern)
-            .map(|p| p.matches(file_path))
-            .unwrap_or(false)
+/// Match a relative path against glob patterns, roughly as `.gitignore` would:
+/// - A pattern without a slash (`*.tmp`, `.DS_Store`) matches the file name in any folder.
+/// - Other patterns are also tried against each sub-path starting at a directory boundary, so
+///   `.git/**` ignores `sub/.git/config` as well as `.git/config`.
+/// - A leading `/` anchors a pattern to the watched folder's root.
+pub fn matches_any_pattern(file_path: &str, patterns: &[String]) -> bool {
+    let file_path = file_path.replace('\\', "/");
+    let file_name = file_path.rsplit('/').next().unwrap_or(&file_path);
+    let sub_paths: Vec<&str> = std::iter::once(file_path.as_str())
+        .chain(file_path.match_indices('/').map(|(i, _)| &file_path[i + 1..]))
+        .collect();
+
+    patterns.iter().any(|pattern| {
+        let (pattern, anchored) = match pattern.strip_prefix('/') {
+            Some(rest) => (rest, true),
+            None => (pattern.as_str(), false),
+        };
+        let basename_only = !anchored && !pattern.contains('/');
+        let Ok(pattern) = glob::Pattern::new(pattern) else {
+            return false;
+        };
+        if basename_only {
+            pattern.matches(file_name)
+        } else if anchored {
+            pattern.matches(&file_path)
+        } else {
+            sub_paths.iter().any(|sub_path| pattern.matches(sub_path))
+        }
     })
 }
 
-fn get_content_type(file_path: &str) -> String {
+/// Content type from `content_type_overrides` if the extension is there, else guessed.
+fn get_content_type(file_path: &str, overrides: &HashMap<String, String>) -> String {
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if let Some(content_type) = extension.and_then(|extension| overrides.get(&extension)) {
+        return content_type.clone();
+    }
     mime_guess::from_path(file_path)
         .first_or_octet_stream()
         .to_string()
 }
 
-pub fn get_relative_path(absolute_path: &str, base_path: &str) -> String {
-    // Try cheap string-based approach first (no filesystem I/O)
-    if absolute_path.starts_with(base_path) {
-        let relative = absolute_path
-            .trim_start_matches(base_path)
-            .trim_start_matches('/')
-            .trim_start_matches('\\');
-        if !relative.is_empty() {
-            return relative.to_string();
-        }
-    }
-
-    // Fall back to canonicalize only when string approach fails (e.g. symlinks)
-    if let Ok(abs) = std::path::Path::new(absolute_path).canonicalize() {
-        if let Ok(base) = std::path::Path::new(base_path).canonicalize() {
-            if let Ok(relative) = abs.strip_prefix(&base) {
-                return relative.to_string_lossy().to_string();
-            }
-        }
+/// Content types that are compressed already, so gzipping them again would waste CPU.
+fn is_compressed_content_type(content_type: &str) -> bool {
+    let (kind, subtype) = content_type.split_once('/').unwrap_or((content_type, ""));
+    match kind {
+        "image" => !matches!(subtype, "svg+xml" | "bmp" | "x-ms-bmp" | "tiff"),
+        "audio" | "video" | "font" => true,
+        _ => matches!(
+            subtype,
+            "zip"
+                | "gzip"
+                | "x-gzip"
+                | "x-bzip2"
+                | "x-xz"
+                | "zstd"
+                | "x-7z-compressed"
+                | "vnd.rar"
+                | "x-rar-compressed"
+                | "java-archive"
+                | "pdf"
+                | "epub+zip"
+        ) || subtype.starts_with("vnd.openxmlformats-officedocument"),
     }
-
-    absolute_path.to_string()
 }
 
-fn compute_crc32c_hash(data: &[u8]) -> String {
-    let hash = crc32c(data);
-    general_purpose::STANDARD.encode(hash.to_be_bytes())
+fn gzip(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
 }
 
-fn system_time_to_iso8601(time: SystemTime) -> Option<String> {
-    let duration = time.duration_since(UNIX_EPOCH).ok()?;
-    let datetime: DateTime<Utc> =
-        DateTime::from_timestamp(duration.as_secs() as i64, duration.subsec_nanos())?;
-    Some(datetime.to_rfc3339())
+fn encryption_cipher(encryption_key: &str) -> Result<Aes256Gcm, String> {
+    let key = general_purpose::STANDARD
+        .decode(encryption_key)
+        .map_err(|e| format!("encryption_key is not valid base64: {e}"))?;
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| format!("encryption_key must be 32 bytes, not {}", key.len()))
 }
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64
+/// Encrypt with a fresh random nonce, returned in front of the ciphertext.
+fn encrypt(content: &[u8], cipher: &Aes256Gcm) -> std::io::Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, content)
+        .map_err(|_| std::io::Error::other("encryption failed"))?;
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
 }
 
-fn get_auth_token(app_handle: &AppHandle) -> Result<Option<String>, String> {
-    let store = app_handle
-        .store(SETTINGS_STORE_FILENAME)
-        .map_err(|e| format!("Failed to access store: {e}"))?;
-    Ok(store
-        .get("token")
-        .and_then(|v| v.as_str().map(String::from)))
+/// Compress then encrypt file content as configured, giving the bytes that will be sent.
+fn encode_content(
+    content: Vec<u8>,
+    compress: bool,
+    cipher: Option<&Aes256Gcm>,
+) -> std::io::Result<Vec<u8>> {
+    let content = if compress { gzip(&content)? } else { content };
+    match cipher {
+        Some(cipher) => encrypt(&content, cipher),
+        None => Ok(content),
+    }
 }
 
-// ── Queue management ────────────────────────────────────────────────────
+/// Canonical form of each watched folder, resolved once since folders rarely move.
+static CANONICAL_BASE_PATHS: std::sync::LazyLock<Mutex<HashMap<String, PathBuf>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
 
-pub fn add_to_upload_queue_sync(
-    file_path: String,
-    base_path: String,
-    upload_queue: &UploadQueue,
-    upload_config: &UploadConfigState,
-    app_handle: &AppHandle,
-) {
-    add_to_upload_queue_with_event_type(
-        file_path,
-        base_path,
-        upload_queue,
-        upload_config,
-        EVENT_TYPE_MODIFIED,
-        app_handle,
-    );
+fn canonical_base_path(base_path: &str) -> Option<PathBuf> {
+    if let Some(canonical) = CANONICAL_BASE_PATHS.lock().get(base_path) {
+        return Some(canonical.clone());
+    }
+    let canonical = Path::new(base_path).canonicalize().ok()?;
+    CANONICAL_BASE_PATHS
+        .lock()
+        .insert(base_path.to_string(), canonical.clone());
+    Some(canonical)
+}
+
+/// Drop a cached canonical folder once it is no longer watched, so watching the same path
+/// again resolves it afresh.
+pub fn forget_canonical_base_path(base_path: &str) {
+    CANONICAL_BASE_PATHS.lock().remove(base_path);
+}
+
+/// Forget which files under `dir` were synced, so the next scan checks each of them with the
+/// server again rather than trusting an unchanged size and mtime.
+pub fn forget_synced_files_under(dir: &str, app_handle: &AppHandle) {
+    if let Some(synced_files) = app_handle.try_state::<SyncedFilesState>() {
+        synced_files
+            .lock()
+            .retain(|path, _| !Path::new(path).starts_with(dir));
+    }
+}
+
+pub fn forget_canonical_base_paths() {
+    CANONICAL_BASE_PATHS.lock().clear();
+}
+
+/// Resolve `.` and `..` segments without touching the filesystem, so it works for paths
+/// that no longer exist.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn strip_base(path: &Path, base: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    Some(relative.to_string_lossy().to_string())
+}
+
+/// Path of `absolute_path` relative to the watched folder `base_path`. The file doesn't have
+/// to exist anymore. Falls back to the absolute path if it isn't under `base_path`.
+pub fn get_relative_path(absolute_path: &str, base_path: &str) -> String {
+    let path = normalize_path(Path::new(absolute_path));
+    if let Some(relative) = strip_base(&path, &normalize_path(Path::new(base_path))) {
+        return relative;
+    }
+
+    // One of the two paths goes through a symlink (e.g. /var and /private/var on macOS) or
+    // differs in case. Compare resolved paths instead, resolving only the file's parent
+    // since the file itself may be gone.
+    if let Some(base) = canonical_base_path(base_path) {
+        if let Some(relative) = strip_base(&path, &base) {
+            return relative;
+        }
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+            if let Ok(parent) = parent.canonicalize() {
+                if let Some(relative) = strip_base(&parent.join(name), &base) {
+                    return relative;
+                }
+            }
+        }
+    }
+
+    absolute_path.to_string()
+}
+
+/// Whether `absolute_path` resolves to somewhere outside `base_path`, i.e. it is reached
+/// through a symlink pointing out of the watched folder.
+fn resolves_outside_base(absolute_path: &str, base_path: &str) -> bool {
+    let Ok(resolved) = Path::new(absolute_path).canonicalize() else {
+        return false;
+    };
+    match canonical_base_path(base_path) {
+        Some(base) => !resolved.starts_with(base),
+        None => false,
+    }
+}
+
+/// Compute the key a file is stored under on the server, applying the configured length
+/// limit. Returns `None` if the key is too long and the file should be skipped.
+fn compute_remote_key(relative_path: &str, config: &UploadConfig) -> Option<String> {
+    let Some(max_len) = config.max_key_length else {
+        return Some(relative_path.to_string());
+    };
+    if relative_path.len() <= max_len {
+        return Some(relative_path.to_string());
+    }
+
+    match config.key_truncation {
+        KeyTruncation::Skip => None,
+        KeyTruncation::Hash => {
+            let digest = Sha256::digest(relative_path.as_bytes());
+            let hash: String = format!("{digest:x}").chars().take(16).collect();
+            let extension = std::path::Path::new(relative_path)
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            let suffix = format!("~{hash}{extension}");
+            if suffix.len() >= max_len {
+                return None;
+            }
+            let mut keep = max_len - suffix.len();
+            while !relative_path.is_char_boundary(keep) {
+                keep -= 1;
+            }
+            Some(format!("{}{}", &relative_path[..keep], suffix))
+        }
+    }
+}
+
+fn compute_crc32c_hash(data: &[u8]) -> String {
+    encode_crc32c(crc32c(data))
+}
+
+fn encode_crc32c(hash: u32) -> String {
+    general_purpose::STANDARD.encode(hash.to_be_bytes())
+}
+
+/// Hash a file in fixed-size chunks so memory use stays bounded regardless of file size.
+async fn compute_crc32c_hash_streaming(path: &str) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; HASH_READ_CHUNK_SIZE];
+    let mut hash = 0u32;
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hash = crc32c_append(hash, &buffer[..read]);
+    }
+    Ok(encode_crc32c(hash))
+}
+
+async fn compute_md5_base64_streaming(path: &str) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; HASH_READ_CHUNK_SIZE];
+    let mut hasher = Md5::new();
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(general_purpose::STANDARD.encode(hasher.finalize()))
+}
+
+/// Name and value of the configured integrity header for an upload, if any.
+async fn integrity_header_for(
+    kind: IntegrityHeader,
+    crc32c: Option<&str>,
+    file_content: Option<&Bytes>,
+    item: &UploadItem,
+) -> Result<Option<(&'static str, String)>, String> {
+    match kind {
+        IntegrityHeader::None => Ok(None),
+        IntegrityHeader::GoogHash => {
+            Ok(crc32c.map(|crc32c| ("x-goog-hash", format!("crc32c={crc32c}"))))
+        }
+        IntegrityHeader::ContentMd5 => {
+            let md5 = match file_content {
+                Some(content) => general_purpose::STANDARD.encode(Md5::digest(content)),
+                None => compute_md5_base64_streaming(&item.path).await.map_err(|e| {
+                    format!("Failed to compute MD5 for '{}': {e}", item.relative_path)
+                })?,
+            };
+            Ok(Some(("Content-MD5", md5)))
+        }
+    }
+}
+
+fn system_time_to_iso8601(time: SystemTime) -> Option<String> {
+    let duration = time.duration_since(UNIX_EPOCH).ok()?;
+    let datetime: DateTime<Utc> =
+        DateTime::from_timestamp(duration.as_secs() as i64, duration.subsec_nanos())?;
+    Some(datetime.to_rfc3339())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Backoff before retry number `attempt + 1`: `retry_delay` doubled per previous attempt,
+/// capped at MAX_RETRY_DELAY, plus up to RETRY_JITTER_FRACTION of random jitter.
+fn retry_backoff(retry_delay: Duration, attempt: u32) -> Duration {
+    let base = retry_delay
+        .checked_mul(2u32.saturating_pow(attempt))
+        .unwrap_or(MAX_RETRY_DELAY)
+        .min(MAX_RETRY_DELAY);
+    let jitter = base.mul_f64(rand::thread_rng().gen_range(0.0..RETRY_JITTER_FRACTION));
+    base + jitter
+}
+
+/// Whether an item has waited at least `delay_ms` since its timestamp (or `max_debounce_ms`
+/// since it was first queued) and is past any retry backoff. A timestamp in the future (the
+/// clock moved backwards since it was queued) counts as ready now, so a clock change never
+/// stalls or breaks the queue.
+fn is_item_ready(
+    item: &UploadItem,
+    now_ms: u64,
+    delay_ms: u64,
+    max_debounce_ms: Option<u64>,
+) -> bool {
+    if now_ms < item.not_before {
+        return false;
+    }
+    if let Some(max_debounce_ms) = max_debounce_ms {
+        if now_ms.saturating_sub(item.first_seen) >= max_debounce_ms {
+            return true;
+        }
+    }
+    match now_ms.checked_sub(item.timestamp) {
+        Some(age_ms) => age_ms >= delay_ms,
+        None => true,
+    }
+}
+
+pub fn get_auth_token(app_handle: &AppHandle) -> Result<Option<String>, String> {
+    let store = app_handle
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to access store: {e}"))?;
+    Ok(store
+        .get("token")
+        .and_then(|v| v.as_str().map(String::from)))
+}
+
+/// The settings store holding the auth token, for callers that must open it before
+/// changing anything else.
+pub fn settings_store(
+    app_handle: &AppHandle,
+) -> Result<Arc<tauri_plugin_store::Store<tauri::Wry>>, String> {
+    app_handle
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to access store: {e}"))
+}
+
+// ── Queue management ────────────────────────────────────────────────────
+
+/// What happened to a path handed to `add_to_upload_queue_with_event_type`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnqueueOutcome {
+    Queued,
+    /// Skipped because it hasn't changed since it was last synced.
+    Unchanged,
+    Ignored,
+    Directory,
+    /// Not queued because the queue is at `max_queue_size`.
+    QueueFull,
+    Error,
+}
+
+fn emit_queue_full(relative_path: &str, config: &UploadConfig, app_handle: &AppHandle) {
+    let _ = app_handle.emit(
+        "queue_full",
+        QueueFullEvent {
+            relative_path: relative_path.to_string(),
+            max_queue_size: config.max_queue_size.unwrap_or_default(),
+            policy: config.queue_full_policy,
+        },
+    );
+    emit_file_upload_status(
+        relative_path,
+        STATUS_QUEUE_FULL,
+        Some("Upload queue is full".to_string()),
+        app_handle,
+    );
+}
+
+/// Check the `.gitignore` and `.syncignore` rules `config` respects for a path under a
+/// watched root.
+pub fn is_excluded_by_ignore_files(
+    file_path: &str,
+    base_path: &str,
+    config: &UploadConfig,
+    app_handle: &AppHandle,
+) -> bool {
+    if !config.respect_gitignore && !config.respect_syncignore {
+        return false;
+    }
+    let Some(cache) = app_handle.try_state::<GitignoreCacheState>() else {
+        return false;
+    };
+    let path = Path::new(file_path);
+    let (base_path, is_dir) = (Path::new(base_path), path.is_dir());
+    (config.respect_gitignore && is_gitignored(path, base_path, is_dir, &cache))
+        || (config.respect_syncignore && is_syncignored(path, base_path, is_dir, &cache))
+}
+
+fn is_upload_in_flight(relative_path: &str, app_handle: &AppHandle) -> bool {
+    app_handle
+        .try_state::<InFlightUploadsState>()
+        .is_some_and(|in_flight| in_flight.lock().contains_key(relative_path))
+}
+
+pub fn add_to_upload_queue_sync(
+    file_path: String,
+    base_path: String,
+    upload_queue: &UploadQueue,
+    upload_config: &UploadConfigState,
+    app_handle: &AppHandle,
+) {
+    add_to_upload_queue_with_event_type(
+        file_path,
+        base_path,
+        upload_queue,
+        upload_config,
+        EVENT_TYPE_MODIFIED,
+        app_handle,
+    );
 }
 
 pub fn add_to_upload_queue_with_event_type(
@@ -293,44 +1753,151 @@ pub fn add_to_upload_queue_with_event_type(
     upload_config: &UploadConfigState,
     event_type: &str,
     app_handle: &AppHandle,
-) {
-    let config = upload_config.lock().clone();
+) -> EnqueueOutcome {
+    let config = effective_upload_config(&base_path, upload_config, app_handle);
     let relative_path = get_relative_path(&file_path, &base_path);
 
+    // The file is back, so it was replaced rather than deleted
+    if let Some(pending_deletes) = app_handle.try_state::<PendingDeletesState>() {
+        pending_deletes.lock().remove(&file_path);
+    }
+
     if !config.enabled {
         debug!("Upload is disabled, skipping file: {file_path}");
         emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
-        return;
+        return EnqueueOutcome::Ignored;
     }
 
-    if config.ignore_existing_files && event_type == EVENT_TYPE_INITIAL {
-        debug!("Ignoring existing file due to ignore_existing_files setting: {file_path}");
+    // A forced upload is one the user asked for despite the ignore settings
+    let forced = event_type == EVENT_TYPE_FORCED;
+
+    if !forced && matches_any_pattern(&relative_path, &config.ignored_patterns) {
+        debug!("File '{relative_path}' matches ignore pattern, skipping upload");
         emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
-        return;
+        return EnqueueOutcome::Ignored;
     }
 
-    if should_ignore_file(&relative_path, &config.ignored_patterns) {
-        debug!("File '{relative_path}' matches ignore pattern, skipping upload");
+    if !config.follow_symlinks && resolves_outside_base(&file_path, &base_path) {
+        debug!("File '{relative_path}' resolves outside {base_path} through a symlink, skipping upload");
         emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
-        return;
+        return EnqueueOutcome::Ignored;
+    }
+
+    if !forced && is_excluded_by_ignore_files(&file_path, &base_path, &config, app_handle) {
+        debug!("File '{relative_path}' is excluded by .gitignore or .syncignore, skipping upload");
+        emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
+        return EnqueueOutcome::Ignored;
     }
 
     // Only queue actual files, not directories
     match std::fs::metadata(&file_path) {
         Ok(metadata) if metadata.is_file() => {
+            if !forced
+                && !config.include_patterns.is_empty()
+                && !matches_any_pattern(&relative_path, &config.include_patterns)
+            {
+                debug!("File '{relative_path}' matches no include pattern, skipping upload");
+                emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
+                return EnqueueOutcome::Ignored;
+            }
+
+            // Directories and files that wouldn't be uploaded anyway never need a key
+            let Some(remote_key) = compute_remote_key(&relative_path, &config) else {
+                let max_key_length = config.max_key_length.unwrap_or_default();
+                warn!(
+                    "Remote key for '{}' is {} bytes (limit {}), skipping upload",
+                    relative_path,
+                    relative_path.len(),
+                    max_key_length
+                );
+                let _ = app_handle.emit(
+                    "key_too_long",
+                    KeyTooLongEvent {
+                        relative_path: relative_path.clone(),
+                        key_length: relative_path.len(),
+                        max_key_length,
+                    },
+                );
+                emit_file_upload_status(&relative_path, STATUS_KEY_TOO_LONG, None, app_handle);
+                return EnqueueOutcome::Ignored;
+            };
+
+            if skip_if_too_large(&relative_path, metadata.len(), &config, app_handle) {
+                return EnqueueOutcome::Ignored;
+            }
+
+            if event_type == EVENT_TYPE_MODIFIED
+                && config.skip_unchanged_modifications
+                && is_unchanged_since_sync(&file_path, &metadata, app_handle)
+            {
+                debug!("File '{relative_path}' unchanged since last sync, skipping upload");
+                return EnqueueOutcome::Unchanged;
+            }
+
+            // The scan runs alongside the watcher, so a file created meanwhile may already
+            // be uploading. The queue itself is deduplicated by path below.
+            if event_type == EVENT_TYPE_INITIAL && is_upload_in_flight(&relative_path, app_handle) {
+                debug!("Existing file '{relative_path}' is already uploading, not queueing again");
+                return EnqueueOutcome::Unchanged;
+            }
+
+            if event_type == EVENT_TYPE_INITIAL
+                && !config.initial_scan_always_verify
+                && is_unchanged_since_sync(&file_path, &metadata, app_handle)
+            {
+                debug!("Existing file '{relative_path}' unchanged since last sync, not verifying");
+                emit_file_upload_status(&relative_path, STATUS_UPLOADED, None, app_handle);
+                return EnqueueOutcome::Unchanged;
+            }
+
+            let now_ms = now_millis();
+            let mut queue = upload_queue.lock();
+            let first_seen = queue
+                .iter()
+                .find(|item| item.path == file_path)
+                .map(|item| item.first_seen);
             let upload_item = UploadItem {
                 path: file_path.clone(),
                 relative_path: relative_path.clone(),
-                timestamp: now_millis(),
+                base_path: base_path.clone(),
+                remote_key,
+                timestamp: now_ms,
+                first_seen: first_seen.unwrap_or(now_ms),
                 retry_count: 0,
+                file_size: metadata.len(),
+                not_before: 0,
+                expiry_requeues: 0,
             };
-
-            let mut queue = upload_queue.lock();
-            let had_duplicate = queue.iter().any(|item| item.path == file_path);
             queue.retain(|item| item.path != file_path);
+
+            // Re-queueing a file already in the queue doesn't grow it
+            let queue_full = first_seen.is_none()
+                && config
+                    .max_queue_size
+                    .is_some_and(|max_queue_size| queue.len() >= max_queue_size);
+            if queue_full {
+                match config.queue_full_policy {
+                    QueueFullPolicy::RejectNew => {
+                        drop(queue);
+                        warn!("Upload queue is full, not queueing '{relative_path}'");
+                        emit_queue_full(&relative_path, &config, app_handle);
+                        return EnqueueOutcome::QueueFull;
+                    }
+                    QueueFullPolicy::DropOldest => {
+                        if let Some(dropped) = queue.pop_front() {
+                            warn!(
+                                "Upload queue is full, dropped '{}' to queue '{relative_path}'",
+                                dropped.relative_path
+                            );
+                            forget_resumable_session(&dropped.path, app_handle);
+                            emit_queue_full(&dropped.relative_path, &config, app_handle);
+                        }
+                    }
+                }
+            }
             queue.push_back(upload_item);
 
-            if had_duplicate {
+            if first_seen.is_some() {
                 debug!(
                     "Updated existing queue item for file: {} (queue size: {})",
                     relative_path,
@@ -345,84 +1912,904 @@ pub fn add_to_upload_queue_with_event_type(
             }
 
             emit_file_upload_status(&relative_path, STATUS_QUEUED, None, app_handle);
+            EnqueueOutcome::Queued
         }
         Ok(metadata) if metadata.is_dir() => {
             debug!("Path '{relative_path}' is a directory, skipping upload");
             emit_file_upload_status(&relative_path, STATUS_DIRECTORY, None, app_handle);
+            EnqueueOutcome::Directory
         }
         Ok(_) => {
             debug!("Path '{relative_path}' is not a file, skipping upload");
             emit_file_upload_status(&relative_path, STATUS_IGNORED, None, app_handle);
+            EnqueueOutcome::Ignored
         }
         Err(e) => {
             warn!("Failed to get metadata for file '{relative_path}': {e}");
+            EnqueueOutcome::Error
         }
     }
 }
 
-// ── Batch presigned URL request ─────────────────────────────────────────
-
-const MAX_CONCURRENT_FILE_READS: usize = 64;
-
-async fn prepare_batch_items(items: Vec<UploadItem>) -> Vec<(PreparedUpload, FileCheckItem)> {
-    stream::iter(items)
-        .map(|item| async move {
-            let file_content: Bytes = match tokio::fs::read(&item.path).await {
-                Ok(content) => content.into(),
-                Err(e) => {
-                    warn!(
-                        "Failed to read file '{}' for batch request: {}",
-                        item.relative_path, e
-                    );
-                    return None;
-                }
-            };
-
-            let content_type = get_content_type(&item.path);
-            let crc32c_hash = compute_crc32c_hash(&file_content);
+/// Handle a local rename by moving the file on the server rather than uploading the same
+/// bytes again under the new name. Falls back to a normal upload of the new path when the
+/// old one hadn't been uploaded yet, or the server can't rename it.
+pub async fn handle_rename(
+    old_path: String,
+    new_path: String,
+    base_path: String,
+    upload_queue: &UploadQueue,
+    upload_config: &UploadConfigState,
+    app_handle: &AppHandle,
+) {
+    // The rename supersedes any pending upload of either path. The new path may have just
+    // been queued by a separate "renamed to" event.
+    let old_was_queued = {
+        let mut queue = upload_queue.lock();
+        let before = queue.len();
+        queue.retain(|item| item.path != old_path);
+        let old_was_queued = queue.len() < before;
+        queue.retain(|item| item.path != new_path);
+        old_was_queued
+    };
 
-            let (file_created_at, file_modified_at) = match tokio::fs::metadata(&item.path).await {
-                Ok(metadata) => (
-                    metadata.created().ok().and_then(system_time_to_iso8601),
-                    metadata.modified().ok().and_then(system_time_to_iso8601),
-                ),
-                Err(e) => {
-                    warn!("Failed to get file timestamps for '{}': {}", item.path, e);
-                    (None, None)
+    let config = effective_upload_config(&base_path, upload_config, app_handle);
+    let old_relative = get_relative_path(&old_path, &base_path);
+    let new_relative = get_relative_path(&new_path, &base_path);
+    let remote_keys =
+        compute_remote_key(&old_relative, &config).zip(compute_remote_key(&new_relative, &config));
+
+    let new_path_ignored = matches_any_pattern(&new_relative, &config.ignored_patterns)
+        || (!config.include_patterns.is_empty()
+            && !matches_any_pattern(&new_relative, &config.include_patterns))
+        || is_excluded_by_ignore_files(&new_path, &base_path, &config, app_handle);
+    let renamable = config.enabled
+        && !config.dry_run
+        && !old_was_queued
+        && std::path::Path::new(&new_path).is_file()
+        && !new_path_ignored;
+
+    if let (true, Some((old_key, new_key))) = (renamable, remote_keys) {
+        match rename_remote_file(&old_key, &new_key, &config, app_handle).await {
+            Ok(()) => {
+                info!("Renamed '{old_relative}' to '{new_relative}' on server");
+                if let Some(synced_files) = app_handle.try_state::<SyncedFilesState>() {
+                    let mut synced = synced_files.lock();
+                    if let Some(fingerprint) = synced.remove(&old_path) {
+                        synced.insert(new_path.clone(), fingerprint);
+                    }
                 }
-            };
+                emit_file_upload_status(&new_relative, STATUS_UPLOADED, None, app_handle);
+                return;
+            }
+            Err(e) => {
+                warn!("Server rename of '{old_relative}' failed, uploading as new file: {e}");
+            }
+        }
+    }
 
-            let check_item = FileCheckItem {
-                file_name: item.relative_path.clone(),
-                content_type: content_type.clone(),
-                crc32c: Some(crc32c_hash),
-                file_created_at,
-                file_modified_at,
-            };
+    add_to_upload_queue_with_event_type(
+        new_path,
+        base_path,
+        upload_queue,
+        upload_config,
+        EVENT_TYPE_CREATED,
+        app_handle,
+    );
+}
 
-            let upload = PreparedUpload {
-                item,
-                file_content,
-                content_type,
-            };
+/// The directory move (old, new) that explains every rename in `renames`, found by
+/// stripping the path components each old and new path end with in common.
+fn common_rename_prefix(renames: &[(String, String)]) -> Option<(PathBuf, PathBuf)> {
+    let mut common: Option<(PathBuf, PathBuf)> = None;
+    for (from, to) in renames {
+        let from: Vec<Component> = Path::new(from).components().collect();
+        let to: Vec<Component> = Path::new(to).components().collect();
+        let shared_tail = from
+            .iter()
+            .rev()
+            .zip(to.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if shared_tail == 0 {
+            return None;
+        }
+        let prefixes = (
+            from[..from.len() - shared_tail].iter().collect::<PathBuf>(),
+            to[..to.len() - shared_tail].iter().collect::<PathBuf>(),
+        );
+        match &common {
+            Some(common) if *common != prefixes => return None,
+            Some(_) => {}
+            None => common = Some(prefixes),
+        }
+    }
+    common
+}
 
-            Some((upload, check_item))
+/// Files under `new_dir` paired with the path each had under `old_dir`.
+fn renames_under(old_dir: &Path, new_dir: &Path) -> Vec<(String, String)> {
+    ignore::WalkBuilder::new(new_dir)
+        .standard_filters(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(new_dir).ok()?;
+            Some((
+                old_dir.join(relative).to_string_lossy().to_string(),
+                entry.path().to_string_lossy().to_string(),
+            ))
         })
-        .buffer_unordered(MAX_CONCURRENT_FILE_READS)
-        .filter_map(|x| async { x })
         .collect()
-        .await
 }
 
-async fn get_presigned_urls_batch(
-    prepared: &[(PreparedUpload, FileCheckItem)],
-    config: &UploadConfig,
-    session_context: &SessionContext,
-    client: &SharedHttpClient,
+/// Handle renames seen within `rename_coalesce_ms` of each other. A renamed directory, or
+/// renames that all move files out of one directory into another, become a single
+/// directory move on the server; anything else is renamed file by file.
+pub async fn handle_renames(
+    renames: Vec<(String, String)>,
+    base_path: String,
+    upload_queue: &UploadQueue,
+    upload_config: &UploadConfigState,
     app_handle: &AppHandle,
-) -> Result<Vec<FileCheckResult>, String> {
-    if prepared.is_empty() {
-        return Ok(vec![]);
+) {
+    let directory_move = match renames.as_slice() {
+        [(from, to)] if Path::new(to).is_dir() => Some((PathBuf::from(from), PathBuf::from(to))),
+        [_] | [] => None,
+        _ => common_rename_prefix(&renames),
+    };
+    let Some((old_dir, new_dir)) = directory_move else {
+        for (from, to) in renames {
+            handle_rename(
+                from,
+                to,
+                base_path.clone(),
+                upload_queue,
+                upload_config,
+                app_handle,
+            )
+            .await;
+        }
+        return;
+    };
+
+    let moved = move_remote_directory(
+        &old_dir,
+        &new_dir,
+        &base_path,
+        upload_queue,
+        upload_config,
+        app_handle,
+    )
+    .await;
+    if !moved {
+        // A lone directory event stands for every file under it
+        let renames = if renames.len() == 1 {
+            renames_under(&old_dir, &new_dir)
+        } else {
+            renames
+        };
+        for (from, to) in renames {
+            handle_rename(
+                from,
+                to,
+                base_path.clone(),
+                upload_queue,
+                upload_config,
+                app_handle,
+            )
+            .await;
+        }
+    }
+}
+
+/// Move everything under `old_dir` to `new_dir` on the server in one request. Returns false,
+/// having changed nothing, if the move isn't possible so the caller can fall back.
+async fn move_remote_directory(
+    old_dir: &Path,
+    new_dir: &Path,
+    base_path: &str,
+    upload_queue: &UploadQueue,
+    upload_config: &UploadConfigState,
+    app_handle: &AppHandle,
+) -> bool {
+    let config = effective_upload_config(base_path, upload_config, app_handle);
+    let (old_dir_str, new_dir_str) = (old_dir.to_string_lossy(), new_dir.to_string_lossy());
+    let old_relative = get_relative_path(&old_dir_str, base_path);
+    let new_relative = get_relative_path(&new_dir_str, base_path);
+    if !config.enabled || config.dry_run || old_relative.is_empty() || new_relative.is_empty() {
+        return false;
+    }
+    // Ignore rules may treat the two directories differently, which only per-file
+    // handling gets right
+    let ignored = |relative: &str, path: &str| {
+        matches_any_pattern(relative, &config.ignored_patterns)
+            || is_excluded_by_ignore_files(path, base_path, &config, app_handle)
+    };
+    if ignored(&old_relative, &old_dir_str) || ignored(&new_relative, &new_dir_str) {
+        return false;
+    }
+    let Some((old_prefix, new_prefix)) =
+        compute_remote_key(&old_relative, &config).zip(compute_remote_key(&new_relative, &config))
+    else {
+        return false;
+    };
+
+    match rename_remote_directory(&old_prefix, &new_prefix, &config, app_handle).await {
+        Ok(true) => {}
+        Ok(false) => {
+            debug!("Server can't move directories, renaming '{old_relative}' file by file");
+            return false;
+        }
+        Err(e) => {
+            warn!("Server move of directory '{old_relative}' failed, renaming file by file: {e}");
+            return false;
+        }
+    }
+    info!("Moved directory '{old_relative}' to '{new_relative}' on server");
+
+    let moved_path = |path: &str| {
+        Path::new(path)
+            .strip_prefix(old_dir)
+            .ok()
+            .map(|rest| new_dir.join(rest).to_string_lossy().to_string())
+    };
+    if let Some(synced_files) = app_handle.try_state::<SyncedFilesState>() {
+        let mut synced = synced_files.lock();
+        let moved: Vec<(String, String)> = synced
+            .keys()
+            .filter_map(|path| Some((path.clone(), moved_path(path)?)))
+            .collect();
+        for (old_path, new_path) in moved {
+            if let Some(fingerprint) = synced.remove(&old_path) {
+                synced.insert(new_path.clone(), fingerprint);
+                let relative = get_relative_path(&new_path, base_path);
+                emit_file_upload_status(&relative, STATUS_UPLOADED, None, app_handle);
+            }
+        }
+    }
+    // Files still waiting to upload were never on the server, so queue them under their new
+    // paths
+    let requeue: Vec<String> = {
+        let mut queue = upload_queue.lock();
+        let (moved, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut *queue)
+            .into_iter()
+            .partition(|item| moved_path(&item.path).is_some());
+        *queue = kept.into();
+        moved
+            .iter()
+            .filter_map(|item| moved_path(&item.path))
+            .collect()
+    };
+    for new_path in requeue {
+        add_to_upload_queue_with_event_type(
+            new_path,
+            base_path.to_string(),
+            upload_queue,
+            upload_config,
+            EVENT_TYPE_CREATED,
+            app_handle,
+        );
+    }
+    true
+}
+
+/// Note a local delete to be synced to the server once it has outlasted `upload_delay_ms`.
+/// Does nothing unless `sync_deletes` is on.
+pub fn queue_delete(
+    file_path: String,
+    base_path: String,
+    upload_config: &UploadConfigState,
+    app_handle: &AppHandle,
+) {
+    let config = effective_upload_config(&base_path, upload_config, app_handle);
+    if !config.enabled || !config.sync_deletes || config.dry_run {
+        return;
+    }
+    let Some(pending_deletes) = app_handle.try_state::<PendingDeletesState>() else {
+        return;
+    };
+
+    let relative_path = get_relative_path(&file_path, &base_path);
+    if matches_any_pattern(&relative_path, &config.ignored_patterns)
+        || (!config.include_patterns.is_empty()
+            && !matches_any_pattern(&relative_path, &config.include_patterns))
+        || is_excluded_by_ignore_files(&file_path, &base_path, &config, app_handle)
+    {
+        return;
+    }
+    let Some(remote_key) = compute_remote_key(&relative_path, &config) else {
+        return;
+    };
+
+    debug!("Queuing delete of '{relative_path}'");
+    pending_deletes.lock().insert(
+        file_path,
+        PendingDelete {
+            relative_path,
+            remote_key,
+            timestamp: now_millis(),
+            retry_count: 0,
+        },
+    );
+}
+
+/// Send the deletes that have waited out `upload_delay_ms` and whose files are still gone.
+fn process_pending_deletes(
+    config: &UploadConfig,
+    http_client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) {
+    let Some(pending_deletes) = app_handle.try_state::<PendingDeletesState>() else {
+        return;
+    };
+    let now_ms = now_millis();
+    let due: Vec<(String, PendingDelete)> = {
+        let mut pending_deletes = pending_deletes.lock();
+        let due_paths: Vec<String> = pending_deletes
+            .iter()
+            .filter(|(_, delete)| now_ms.saturating_sub(delete.timestamp) >= config.upload_delay_ms)
+            .map(|(path, _)| path.clone())
+            .collect();
+        due_paths
+            .into_iter()
+            .filter_map(|path| pending_deletes.remove_entry(&path))
+            .collect()
+    };
+
+    for (file_path, mut delete) in due {
+        if std::path::Path::new(&file_path).exists() {
+            continue;
+        }
+        let config = config.clone();
+        let http_client = http_client.clone();
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            match delete_remote_file(&delete.remote_key, &config, &http_client, &app_handle).await {
+                Ok(()) => info!("Deleted '{}' on server", delete.relative_path),
+                Err(e) => {
+                    delete.retry_count += 1;
+                    if delete.retry_count < config.max_retry_count {
+                        let message = format!(
+                            "Server delete of '{}' failed (attempt {}/{}), will retry: {e}",
+                            delete.relative_path, delete.retry_count, config.max_retry_count
+                        );
+                        warn!("{message}");
+                        record_activity(&app_handle, LEVEL_WARN, message);
+                        delete.timestamp = now_millis();
+                        if let Some(pending_deletes) = app_handle.try_state::<PendingDeletesState>()
+                        {
+                            // A newer delete of the same path takes precedence
+                            pending_deletes.lock().entry(file_path).or_insert(delete);
+                        }
+                    } else {
+                        let message = format!(
+                            "Server delete of '{}' permanently failed after {} attempts: {e}",
+                            delete.relative_path, config.max_retry_count
+                        );
+                        error!("{message}");
+                        record_activity(&app_handle, LEVEL_ERROR, message);
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn delete_remote_file(
+    remote_key: &str,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let token = get_auth_token(app_handle)?;
+    let delete_url = format!("{}/api/sync/delete", config.server_url);
+
+    let mut request = client
+        .post(&delete_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers())
+        .json(&DeleteFileBody {
+            file_name: remote_key.to_string(),
+            device_fingerprint: crate::compute_device_fingerprint().ok(),
+        });
+    if let Some(token_str) = &token {
+        request = request.header("Authorization", format!("Bearer {token_str}"));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send delete request for '{remote_key}': {e}"))?;
+    // Already gone, or never uploaded
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(());
+    }
+    check_response(response, &format!("Delete of '{remote_key}'")).await?;
+    Ok(())
+}
+
+async fn rename_remote_file(
+    old_key: &str,
+    new_key: &str,
+    config: &UploadConfig,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let client = app_handle
+        .try_state::<SharedHttpClient>()
+        .ok_or("HTTP client not available")?;
+    let token = get_auth_token(app_handle)?;
+    let rename_url = format!("{}/api/sync/rename", config.server_url);
+
+    let mut request = client
+        .post(&rename_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers())
+        .json(&RenameFileBody {
+            old_file_name: old_key.to_string(),
+            new_file_name: new_key.to_string(),
+        });
+    if let Some(token_str) = &token {
+        request = request.header("Authorization", format!("Bearer {token_str}"));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send rename request for '{old_key}': {e}"))?;
+
+    check_response(response, &format!("Rename of '{old_key}'")).await?;
+    Ok(())
+}
+
+/// Move every file under `old_prefix` to `new_prefix`. Returns false if the server has no
+/// directory move support.
+async fn rename_remote_directory(
+    old_prefix: &str,
+    new_prefix: &str,
+    config: &UploadConfig,
+    app_handle: &AppHandle,
+) -> Result<bool, String> {
+    let client = app_handle
+        .try_state::<SharedHttpClient>()
+        .ok_or("HTTP client not available")?;
+    let token = get_auth_token(app_handle)?;
+    let rename_url = format!("{}/api/sync/rename-directory", config.server_url);
+
+    let mut request = client
+        .post(&rename_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers())
+        .json(&RenameDirectoryBody {
+            old_prefix: old_prefix.to_string(),
+            new_prefix: new_prefix.to_string(),
+        });
+    if let Some(token_str) = &token {
+        request = request.header("Authorization", format!("Bearer {token_str}"));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send directory move request for '{old_prefix}': {e}"))?;
+    if matches!(
+        response.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+    ) {
+        return Ok(false);
+    }
+
+    check_response(response, &format!("Directory move of '{old_prefix}'")).await?;
+    Ok(true)
+}
+
+/// Report and skip a file larger than `max_file_size_bytes`.
+fn skip_if_too_large(
+    relative_path: &str,
+    size: u64,
+    config: &UploadConfig,
+    app_handle: &AppHandle,
+) -> bool {
+    match config.max_file_size_bytes {
+        Some(max_size) if size > max_size => {
+            info!("Skipping '{relative_path}': {size} bytes exceeds the {max_size} byte limit");
+            emit_file_upload_status(relative_path, STATUS_IGNORED, None, app_handle);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Record a file that ran out of retries. Any resumable session it left is dropped, since a
+/// manual retry starts over.
+fn record_failed_upload(item: &UploadItem, error: &str, app_handle: &AppHandle) {
+    if let Some(failed_uploads) = app_handle.try_state::<FailedUploadsState>() {
+        failed_uploads.lock().insert(
+            item.path.clone(),
+            FailedUpload {
+                item: item.clone(),
+                error: error.to_string(),
+            },
+        );
+    }
+    forget_resumable_session(&item.path, app_handle);
+}
+
+/// Drop the resumable session a file left unfinished, once it won't be uploaded again.
+fn forget_resumable_session(file_path: &str, app_handle: &AppHandle) {
+    if let Some(sessions) = app_handle.try_state::<ResumableSessionsState>() {
+        sessions.lock().remove(file_path);
+    }
+}
+
+fn clear_failed_upload(file_path: &str, app_handle: &AppHandle) {
+    if let Some(failed_uploads) = app_handle.try_state::<FailedUploadsState>() {
+        failed_uploads.lock().remove(file_path);
+    }
+}
+
+fn is_unchanged_since_sync(
+    file_path: &str,
+    metadata: &std::fs::Metadata,
+    app_handle: &AppHandle,
+) -> bool {
+    let Some(synced_files) = app_handle.try_state::<SyncedFilesState>() else {
+        return false;
+    };
+    let synced = synced_files.lock();
+    synced.get(file_path) == Some(&FileFingerprint::from_metadata(metadata))
+}
+
+fn record_synced_file(synced_files: &SyncedFilesState, prepared: &PreparedUpload) {
+    if let Some(fingerprint) = prepared.fingerprint {
+        synced_files
+            .lock()
+            .insert(prepared.item.path.clone(), fingerprint);
+    }
+}
+
+/// Delete a local file after the configured delay, provided it still matches the
+/// fingerprint that was uploaded and isn't protected.
+fn schedule_local_delete(
+    item: UploadItem,
+    fingerprint: Option<FileFingerprint>,
+    config: &UploadConfig,
+    app_handle: &AppHandle,
+) {
+    let Some(fingerprint) = fingerprint else {
+        warn!(
+            "Not deleting '{}': no fingerprint recorded at upload time",
+            item.relative_path
+        );
+        return;
+    };
+    if matches_any_pattern(&item.relative_path, &config.delete_protected_patterns) {
+        debug!("Not deleting '{}': matches protected pattern", item.relative_path);
+        return;
+    }
+
+    let delay = Duration::from_secs(config.delete_after_upload_delay_secs);
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        sleep(delay).await;
+
+        let current = match tokio::fs::metadata(&item.path).await {
+            Ok(metadata) => FileFingerprint::from_metadata(&metadata),
+            Err(e) => {
+                debug!("Not deleting '{}': {e}", item.relative_path);
+                return;
+            }
+        };
+        if current != fingerprint {
+            info!(
+                "Not deleting '{}': file changed since it was uploaded",
+                item.relative_path
+            );
+            return;
+        }
+
+        match tokio::fs::remove_file(&item.path).await {
+            Ok(()) => {
+                info!("Deleted local file after upload: {}", item.relative_path);
+                if let Some(synced_files) = app_handle.try_state::<SyncedFilesState>() {
+                    synced_files.lock().remove(&item.path);
+                }
+                let _ = app_handle.emit("local_file_deleted", &item.relative_path);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to delete local file '{}' after upload: {e}",
+                    item.relative_path
+                );
+            }
+        }
+    });
+}
+
+const LAST_SYNC_STORE_KEY: &str = "last_sync_times";
+
+/// Record a successful sync for the item's watched root, persist it and notify the frontend.
+fn record_last_sync(item: &UploadItem, app_handle: &AppHandle) {
+    let Some(last_sync) = app_handle.try_state::<LastSyncState>() else {
+        return;
+    };
+    let timestamp = now_millis();
+    let snapshot = {
+        let mut last_sync = last_sync.lock();
+        last_sync.insert(item.base_path.clone(), timestamp);
+        last_sync.clone()
+    };
+
+    if let Ok(store) = app_handle.store(SETTINGS_STORE_FILENAME) {
+        store.set(
+            LAST_SYNC_STORE_KEY,
+            serde_json::to_value(&snapshot).unwrap_or_default(),
+        );
+    }
+
+    let _ = app_handle.emit(
+        "last_sync_updated",
+        LastSyncUpdatedEvent {
+            folder: item.base_path.clone(),
+            timestamp,
+        },
+    );
+}
+
+/// Restore the per-folder last sync times from the Tauri Store on startup.
+pub fn restore_last_sync_times(app_handle: &AppHandle) -> HashMap<String, u64> {
+    app_handle
+        .store(SETTINGS_STORE_FILENAME)
+        .ok()
+        .and_then(|store| store.get(LAST_SYNC_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+// ── Batch presigned URL request ─────────────────────────────────────────
+
+const MAX_CONCURRENT_FILE_READS: usize = 64;
+
+/// A file that couldn't be read for its batch request.
+type ReadFailure = (UploadItem, std::io::Error);
+
+async fn prepare_batch_items(
+    items: Vec<UploadItem>,
+    config: &UploadConfig,
+) -> (Vec<(PreparedUpload, FileCheckItem)>, Vec<ReadFailure>) {
+    let max_memory_bytes = config.max_upload_memory_bytes;
+    // An invalid key fails every file rather than uploading it unencrypted
+    let cipher = config
+        .encryption_key
+        .as_deref()
+        .map(|key| encryption_cipher(key).map_err(std::io::Error::other));
+    let cipher = cipher.as_ref();
+
+    stream::iter(items)
+        .map(|item| async move {
+            let metadata = match tokio::fs::metadata(&item.path).await {
+                Ok(metadata) => Some(metadata),
+                Err(e) => {
+                    warn!("Failed to get file timestamps for '{}': {}", item.path, e);
+                    None
+                }
+            };
+            let (file_created_at, file_modified_at) = match &metadata {
+                Some(metadata) => (
+                    metadata.created().ok().and_then(system_time_to_iso8601),
+                    metadata.modified().ok().and_then(system_time_to_iso8601),
+                ),
+                None => (None, None),
+            };
+
+            // Files larger than the whole memory budget are hashed in chunks and not kept around
+            let too_large = metadata
+                .as_ref()
+                .is_some_and(|m| m.len() > max_memory_bytes);
+            let content_type = get_content_type(&item.path, &config.content_type_overrides);
+            // Only content kept in memory is compressed, so what's hashed is what gets sent
+            let compress =
+                config.compress_uploads && !too_large && !is_compressed_content_type(&content_type);
+
+            let read_result = if let Some(Err(e)) = cipher {
+                Err(std::io::Error::other(format!("can't encrypt: {e}")))
+            } else if too_large && cipher.is_some() {
+                Err(std::io::Error::other(
+                    "too large to encrypt within max_upload_memory_bytes",
+                ))
+            } else if too_large {
+                debug!(
+                    "File '{}' exceeds batch memory budget, hashing in chunks",
+                    item.relative_path
+                );
+                compute_crc32c_hash_streaming(&item.path)
+                    .await
+                    .map(|hash| (None, hash, None))
+            } else {
+                let cipher = cipher.and_then(|cipher| cipher.as_ref().ok()).cloned();
+                match tokio::fs::read(&item.path).await {
+                    Ok(content) if compress || cipher.is_some() => {
+                        tokio::task::spawn_blocking(move || {
+                            let plain_hash =
+                                cipher.is_some().then(|| compute_crc32c_hash(&content));
+                            encode_content(content, compress, cipher.as_ref())
+                                .map(|content| (content, plain_hash))
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+                    }
+                    result => result.map(|content| (content, None)),
+                }
+                .map(|(content, plain_hash)| {
+                    let hash = compute_crc32c_hash(&content);
+                    let content = Some(Bytes::from(content));
+                    match plain_hash {
+                        Some(plain_hash) => (content, plain_hash, Some(hash)),
+                        None => (content, hash, None),
+                    }
+                })
+            };
+
+            let (file_content, crc32c_hash, body_crc32c) = match read_result {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!(
+                        "Failed to read file '{}' for batch request: {}",
+                        item.relative_path, e
+                    );
+                    return Err((item, e));
+                }
+            };
+
+            let content_encoding = compress.then(|| "gzip".to_string());
+
+            let check_item = FileCheckItem {
+                file_name: item.remote_key.clone(),
+                content_type: content_type.clone(),
+                crc32c: Some(crc32c_hash),
+                file_created_at,
+                file_modified_at,
+                content_encoding: content_encoding.clone(),
+                encrypted: cipher.is_some(),
+            };
+
+            let upload = PreparedUpload {
+                item,
+                file_content,
+                content_type,
+                content_encoding,
+                fingerprint: metadata.as_ref().map(FileFingerprint::from_metadata),
+                body_crc32c,
+            };
+
+            Ok((upload, check_item))
+        })
+        .buffer_unordered(MAX_CONCURRENT_FILE_READS)
+        .fold(
+            (Vec::new(), Vec::new()),
+            |(mut prepared, mut failures), result| async move {
+                match result {
+                    Ok(upload) => prepared.push(upload),
+                    Err(failure) => failures.push(failure),
+                }
+                (prepared, failures)
+            },
+        )
+        .await
+}
+
+/// Device + inode of a file with more than one link. Identities from different devices
+/// never compare equal, so those files fall back to being hashed individually.
+#[cfg(unix)]
+fn hard_link_identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn hard_link_identity(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Like `prepare_batch_items`, but reads each hard-linked file only once and copies the
+/// result to its other paths in the batch, unless their content type compresses differently.
+async fn prepare_batch_items_sharing_links(
+    items: Vec<UploadItem>,
+    config: &UploadConfig,
+) -> (Vec<(PreparedUpload, FileCheckItem)>, Vec<ReadFailure>) {
+    let mut primaries: Vec<UploadItem> = Vec::new();
+    let mut primary_by_identity: HashMap<(u64, u64), String> = HashMap::new();
+    let mut links: Vec<(UploadItem, String)> = Vec::new();
+
+    for item in items {
+        let identity = tokio::fs::metadata(&item.path)
+            .await
+            .ok()
+            .and_then(|m| hard_link_identity(&m));
+        match identity.and_then(|id| primary_by_identity.get(&id)) {
+            Some(primary_path) => {
+                debug!(
+                    "File '{}' is a hard link of '{}', reusing its hash",
+                    item.relative_path, primary_path
+                );
+                links.push((item, primary_path.clone()));
+            }
+            None => {
+                if let Some(id) = identity {
+                    primary_by_identity.insert(id, item.path.clone());
+                }
+                primaries.push(item);
+            }
+        }
+    }
+
+    let (mut prepared, mut failures) = prepare_batch_items(primaries, config).await;
+    let mut unshared = Vec::new();
+    for (item, primary_path) in links {
+        let Some((primary, primary_check)) = prepared
+            .iter()
+            .find(|(upload, _)| upload.item.path == primary_path)
+        else {
+            // The link shares its primary's read error
+            let error = failures
+                .iter()
+                .find(|(failed, _)| failed.path == primary_path)
+                .map(|(_, e)| std::io::Error::new(e.kind(), e.to_string()))
+                .unwrap_or_else(|| std::io::Error::other("hard link couldn't be read"));
+            failures.push((item, error));
+            continue;
+        };
+        // A link with another extension can have another content type, and so be compressed
+        // differently; the primary's encoded bytes only fit if it isn't
+        let content_type = get_content_type(&item.path, &config.content_type_overrides);
+        let compress = config.compress_uploads
+            && primary.file_content.is_some()
+            && !is_compressed_content_type(&content_type);
+        if compress != primary.content_encoding.is_some() {
+            unshared.push(item);
+            continue;
+        }
+        let check_item = FileCheckItem {
+            file_name: item.remote_key.clone(),
+            content_type: content_type.clone(),
+            crc32c: primary_check.crc32c.clone(),
+            file_created_at: primary_check.file_created_at.clone(),
+            file_modified_at: primary_check.file_modified_at.clone(),
+            content_encoding: primary_check.content_encoding.clone(),
+            encrypted: primary_check.encrypted,
+        };
+        let upload = PreparedUpload {
+            item,
+            file_content: primary.file_content.clone(),
+            content_type,
+            content_encoding: primary.content_encoding.clone(),
+            fingerprint: primary.fingerprint,
+            body_crc32c: primary.body_crc32c.clone(),
+        };
+        prepared.push((upload, check_item));
+    }
+    if !unshared.is_empty() {
+        let (unshared_prepared, unshared_failures) = prepare_batch_items(unshared, config).await;
+        prepared.extend(unshared_prepared);
+        failures.extend(unshared_failures);
+    }
+    (prepared, failures)
+}
+
+async fn get_presigned_urls_batch(
+    prepared: &[(PreparedUpload, FileCheckItem)],
+    config: &UploadConfig,
+    session_context: &SessionContext,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<Vec<FileCheckResult>, String> {
+    if prepared.is_empty() {
+        return Ok(vec![]);
     }
 
     info!(
@@ -438,143 +2825,1110 @@ async fn get_presigned_urls_batch(
             crc32c: check.crc32c.clone(),
             file_created_at: check.file_created_at.clone(),
             file_modified_at: check.file_modified_at.clone(),
+            content_encoding: check.content_encoding.clone(),
+            encrypted: check.encrypted,
         })
         .collect();
 
     let token = get_auth_token(app_handle)?;
-    let batch_url = format!("{}/api/sync/upload-urls", config.server_url);
+    let batch_url = format!("{}/api/sync/upload-urls", config.server_url);
+
+    debug!(
+        "Sending batch request to: {} with {} files",
+        batch_url,
+        file_check_items.len()
+    );
+
+    let (active_user_id, active_metadata) = session_context.active_fields();
+    let body = GetPresignedBatchBody {
+        files: file_check_items,
+        session_user_id: active_user_id.cloned(),
+        session_metadata: active_metadata.cloned(),
+    };
+    let build_request = |token: Option<&str>| {
+        let request = client
+            .post(&batch_url)
+            .timeout(config.request_timeout())
+            .headers(config.server_headers())
+            .json(&body);
+        match token {
+            Some(token_str) => request.header("Authorization", format!("Bearer {token_str}")),
+            None => request,
+        }
+    };
+
+    let (response, _) = send_with_token_refresh(token, build_request, client, app_handle)
+        .await
+        .map_err(|e| format!("Failed to send batch presigned request: {e}"))?;
+
+    let response = check_response(response, "Batch presigned request").await?;
+
+    let batch_response: GetPresignedBatchResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse batch presigned response: {e}"))?;
+
+    info!(
+        "Batch request successful: {} files processed",
+        batch_response.files.len()
+    );
+
+    Ok(batch_response.files)
+}
+
+// ── Single file upload ──────────────────────────────────────────────────
+
+type BodyChunks = BoxStream<'static, std::io::Result<Bytes>>;
+
+/// Emit `file_upload_bytes` as chunks of the body are handed to the connection, at most a
+/// few times a second plus once when the last chunk goes out.
+fn with_upload_progress<S>(
+    body: S,
+    relative_path: String,
+    already_uploaded: u64,
+    total: u64,
+    app_handle: AppHandle,
+) -> impl Stream<Item = std::io::Result<Bytes>>
+where
+    S: Stream<Item = std::io::Result<Bytes>>,
+{
+    let mut uploaded = already_uploaded;
+    let mut last_emit: Option<Instant> = None;
+    body.inspect(move |chunk| {
+        let Ok(chunk) = chunk else {
+            return;
+        };
+        uploaded += chunk.len() as u64;
+        if uploaded >= total
+            || last_emit.is_none_or(|emitted| emitted.elapsed() >= UPLOAD_BYTES_EMIT_INTERVAL)
+        {
+            last_emit = Some(Instant::now());
+            let _ = app_handle.emit(
+                "file_upload_bytes",
+                FileUploadBytesEvent {
+                    relative_path: relative_path.clone(),
+                    uploaded,
+                    total,
+                },
+            );
+        }
+    })
+}
+
+/// Split in-memory content into body chunks without copying.
+fn body_chunks(content: Bytes) -> BodyChunks {
+    let chunks: Vec<std::io::Result<Bytes>> = (0..content.len())
+        .step_by(UPLOAD_BODY_CHUNK_SIZE)
+        .map(|start| Ok(content.slice(start..(start + UPLOAD_BODY_CHUNK_SIZE).min(content.len()))))
+        .collect();
+    stream::iter(chunks).boxed()
+}
+
+/// PUT the file content to the presigned URL. This is the only part that
+/// should be held under the concurrency semaphore.
+#[allow(clippy::too_many_arguments)]
+async fn upload_file_put(
+    item: &UploadItem,
+    file_content: Option<Bytes>,
+    content_type: &str,
+    content_encoding: Option<&str>,
+    integrity_header: Option<(&str, String)>,
+    upload_url: &str,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<HeaderMap, UploadError> {
+    info!(
+        "Starting upload for file: {} (attempt: {})",
+        item.relative_path,
+        item.retry_count + 1
+    );
+
+    emit_file_upload_status(&item.relative_path, STATUS_UPLOADING, None, app_handle);
+
+    // Content not kept in memory from the batch step is streamed from disk so memory
+    // stays bounded regardless of file size. In-memory content is sliced into chunks (without
+    // copying) so progress is reported the same way for both.
+    let (chunks, file_size): (BodyChunks, u64) = match file_content {
+        Some(content) => {
+            let size = content.len() as u64;
+            (body_chunks(content), size)
+        }
+        None => {
+            let file = tokio::fs::File::open(&item.path)
+                .await
+                .map_err(|e| format!("Failed to open file '{}': {}", item.relative_path, e))?;
+            let size = file
+                .metadata()
+                .await
+                .map_err(|e| format!("Failed to stat file '{}': {}", item.relative_path, e))?
+                .len();
+            (ReaderStream::new(file).boxed(), size)
+        }
+    };
+    let body = Body::wrap_stream(with_upload_progress(
+        chunks,
+        item.relative_path.clone(),
+        0,
+        file_size,
+        app_handle.clone(),
+    ));
+
+    debug!(
+        "Uploading {} bytes for file: {}",
+        file_size, item.relative_path
+    );
+
+    let mut request = client
+        .put(upload_url)
+        .header("Content-Type", content_type)
+        .header(CONTENT_LENGTH, file_size);
+    if let Some(content_encoding) = content_encoding {
+        request = request.header(CONTENT_ENCODING, content_encoding);
+    }
+    if let Some((name, value)) = integrity_header {
+        request = request.header(name, value);
+    }
+
+    let response = request.body(body).send().await.map_err(|e| {
+        UploadError::network(format!(
+            "Failed to upload file '{}' to presigned URL: {}",
+            item.relative_path, e
+        ))
+    })?;
+
+    let response =
+        check_upload_response(response, &format!("Upload for '{}'", item.relative_path)).await?;
+
+    info!(
+        "Successfully uploaded file: {} ({} bytes)",
+        item.relative_path, file_size
+    );
+
+    Ok(response.headers().clone())
+}
+
+/// CRC32C from `x-goog-hash` headers, each of which may list several comma-separated hashes.
+fn reported_crc32c(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all("x-goog-hash")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|hash| hash.trim().strip_prefix("crc32c=").map(str::to_string))
+}
+
+/// Check the CRC32C the storage backend reports for an uploaded file against the one that was
+/// sent. Uploads the backend gives no CRC32C for are trusted.
+async fn verify_stored_crc32c(
+    item: &UploadItem,
+    expected_crc32c: &str,
+    put_headers: &HeaderMap,
+    verify_url: Option<&str>,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(), UploadError> {
+    let reported = match (reported_crc32c(put_headers), verify_url) {
+        (Some(reported), _) => Some(reported),
+        (None, Some(verify_url)) => {
+            let response = client
+                .head(verify_url)
+                .timeout(config.request_timeout())
+                .send()
+                .await
+                .map_err(|e| {
+                    UploadError::network(format!(
+                        "Failed to check stored copy of '{}': {e}",
+                        item.relative_path
+                    ))
+                })?;
+            let response = check_upload_response(
+                response,
+                &format!("Verification of '{}'", item.relative_path),
+            )
+            .await?;
+            reported_crc32c(response.headers())
+        }
+        (None, None) => None,
+    };
+    let Some(reported) = reported else {
+        debug!(
+            "No CRC32C reported for '{}', skipping verification",
+            item.relative_path
+        );
+        return Ok(());
+    };
+    if reported == expected_crc32c {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Stored copy of '{}' has CRC32C {reported} but {expected_crc32c} was sent",
+        item.relative_path
+    );
+    error!("{message}");
+    record_activity(app_handle, LEVEL_ERROR, message.clone());
+    Err(UploadError::server(message))
+}
+
+// ── Multipart upload ────────────────────────────────────────────────────
+
+/// Ask the server for a presigned URL per part. Returns None if the server has no
+/// multipart support.
+async fn get_multipart_urls(
+    file_id: &str,
+    part_count: u64,
+    part_size: u64,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<Option<MultipartUrlsResponse>, UploadError> {
+    let token = get_auth_token(app_handle)?;
+    let multipart_url = format!("{}/api/sync/{}/multipart-urls", config.server_url, file_id);
+
+    let mut request = client
+        .post(&multipart_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers())
+        .json(&MultipartUrlsBody {
+            part_count,
+            part_size,
+        });
+    if let Some(token_str) = &token {
+        request = request.header("Authorization", format!("Bearer {token_str}"));
+    }
 
-    debug!(
-        "Sending batch request to: {} with {} files",
-        batch_url,
-        file_check_items.len()
-    );
+    let response = request.send().await.map_err(|e| {
+        UploadError::network(format!(
+            "Failed to request multipart URLs for file ID '{file_id}': {e}"
+        ))
+    })?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = check_upload_response(response, "Multipart URL request").await?;
 
-    let (active_user_id, active_metadata) = session_context.active_fields();
-    let mut request = client.post(&batch_url).json(&GetPresignedBatchBody {
-        files: file_check_items,
-        session_user_id: active_user_id.cloned(),
-        session_metadata: active_metadata.cloned(),
-    });
+    let multipart: MultipartUrlsResponse = response
+        .json()
+        .await
+        .map_err(|e| UploadError::server(format!("Failed to parse multipart URL response: {e}")))?;
+    if multipart.urls.len() as u64 != part_count {
+        return Err(UploadError::server(format!(
+            "Server returned {} multipart URLs for {part_count} parts",
+            multipart.urls.len()
+        )));
+    }
+    Ok(Some(multipart))
+}
+
+async fn complete_multipart(
+    file_id: &str,
+    upload_id: String,
+    parts: Vec<CompletedPart>,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(), UploadError> {
+    let token = get_auth_token(app_handle)?;
+    let complete_url = format!(
+        "{}/api/sync/{}/complete-multipart",
+        config.server_url, file_id
+    );
 
+    let mut request = client
+        .post(&complete_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers())
+        .json(&CompleteMultipartBody { upload_id, parts });
     if let Some(token_str) = &token {
         request = request.header("Authorization", format!("Bearer {token_str}"));
     }
 
-    let response = request
+    let response = request.send().await.map_err(|e| {
+        UploadError::network(format!(
+            "Failed to complete multipart upload for file ID '{file_id}': {e}"
+        ))
+    })?;
+    check_upload_response(
+        response,
+        &format!("Multipart completion for file ID '{file_id}'"),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Body for one part, sliced from in-memory content or streamed from its offset on disk.
+async fn part_body(
+    item: &UploadItem,
+    file_content: Option<&Bytes>,
+    offset: u64,
+    len: u64,
+) -> Result<BodyChunks, String> {
+    if let Some(content) = file_content {
+        return Ok(body_chunks(
+            content.slice(offset as usize..(offset + len) as usize),
+        ));
+    }
+    let mut file = tokio::fs::File::open(&item.path)
+        .await
+        .map_err(|e| format!("Failed to open file '{}': {}", item.relative_path, e))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("Failed to seek in file '{}': {}", item.relative_path, e))?;
+    Ok(ReaderStream::new(file.take(len)).boxed())
+}
+
+/// PUT one part and return the ETag the server needs to assemble the file.
+async fn upload_part(
+    url: &str,
+    body: BodyChunks,
+    len: u64,
+    client: &SharedHttpClient,
+) -> Result<String, UploadError> {
+    let response = client
+        .put(url)
+        .header(CONTENT_LENGTH, len)
+        .body(Body::wrap_stream(body))
         .send()
         .await
-        .map_err(|e| format!("Failed to send batch presigned request: {e}"))?;
+        .map_err(|e| UploadError::network(format!("Failed to upload part: {e}")))?;
+    let response = check_upload_response(response, "Part upload").await?;
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| UploadError::server("Part upload response has no ETag".to_string()))
+}
 
-    let response = check_response(response, "Batch presigned request").await?;
+#[allow(clippy::too_many_arguments)]
+async fn upload_part_with_retries(
+    item: &UploadItem,
+    file_content: Option<&Bytes>,
+    part_number: usize,
+    url: &str,
+    offset: u64,
+    len: u64,
+    upload_timeout: Duration,
+    client: &SharedHttpClient,
+) -> Result<String, UploadError> {
+    let mut attempt = 1;
+    loop {
+        let body = part_body(item, file_content, offset, len).await?;
+        let result = tokio::time::timeout(upload_timeout, upload_part(url, body, len, client))
+            .await
+            .unwrap_or_else(|_| {
+                Err(UploadError::network(format!(
+                    "timed out after {}s",
+                    upload_timeout.as_secs()
+                )))
+            });
+        let e = match result {
+            Ok(etag) => return Ok(etag),
+            Err(e) => e,
+        };
+        warn!(
+            "Part {part_number} of '{}' failed (attempt {attempt}/{}): {e}",
+            item.relative_path, MULTIPART_PART_ATTEMPTS
+        );
+        if attempt >= MULTIPART_PART_ATTEMPTS {
+            return Err(UploadError {
+                message: format!(
+                    "Failed to upload part {part_number} of '{}': {e}",
+                    item.relative_path
+                ),
+                ..e
+            });
+        }
+        attempt += 1;
+    }
+}
 
-    let batch_response: GetPresignedBatchResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse batch presigned response: {e}"))?;
+/// Upload the file in parts, each under an upload permit and retried a few times on its own.
+/// Returns false, having uploaded nothing, if the server doesn't support multipart uploads.
+async fn upload_file_multipart(
+    item: &UploadItem,
+    file_id: &str,
+    file_content: Option<Bytes>,
+    config: &UploadConfig,
+    semaphore: &Arc<Semaphore>,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<bool, UploadError> {
+    let file_size = match &file_content {
+        Some(content) => content.len() as u64,
+        None => tokio::fs::metadata(&item.path)
+            .await
+            .map_err(|e| format!("Failed to stat file '{}': {}", item.relative_path, e))?
+            .len(),
+    };
+    let part_size = MULTIPART_PART_SIZE.max(file_size.div_ceil(MAX_MULTIPART_PARTS));
+    let part_count = file_size.div_ceil(part_size).max(1);
+
+    let Some(multipart) =
+        get_multipart_urls(file_id, part_count, part_size, config, client, app_handle).await?
+    else {
+        return Ok(false);
+    };
 
     info!(
-        "Batch request successful: {} files processed",
-        batch_response.files.len()
+        "Starting multipart upload for file: {} ({part_count} parts, attempt: {})",
+        item.relative_path,
+        item.retry_count + 1
     );
+    emit_file_upload_status(&item.relative_path, STATUS_UPLOADING, None, app_handle);
 
-    Ok(batch_response.files)
+    let upload_timeout = Duration::from_secs(config.upload_timeout_secs);
+    let uploaded = AtomicU64::new(0);
+    let mut parts: Vec<CompletedPart> = stream::iter(multipart.urls.into_iter().enumerate())
+        .map(|(index, url)| {
+            let file_content = file_content.as_ref();
+            let uploaded = &uploaded;
+            async move {
+                let _permit = semaphore.clone().acquire_owned().await.unwrap();
+                let offset = index as u64 * part_size;
+                let len = part_size.min(file_size - offset);
+                let part_number = index + 1;
+                let etag = upload_part_with_retries(
+                    item,
+                    file_content,
+                    part_number,
+                    &url,
+                    offset,
+                    len,
+                    upload_timeout,
+                    client,
+                )
+                .await?;
+
+                let total_uploaded = uploaded.fetch_add(len, Ordering::Relaxed) + len;
+                let _ = app_handle.emit(
+                    "file_upload_bytes",
+                    FileUploadBytesEvent {
+                        relative_path: item.relative_path.clone(),
+                        uploaded: total_uploaded,
+                        total: file_size,
+                    },
+                );
+                Ok::<_, UploadError>(CompletedPart { part_number, etag })
+            }
+        })
+        .buffer_unordered(config.parallel_parts.min(config.max_concurrent_uploads))
+        .try_collect()
+        .await?;
+
+    parts.sort_by_key(|part| part.part_number);
+    complete_multipart(
+        file_id,
+        multipart.upload_id,
+        parts,
+        config,
+        client,
+        app_handle,
+    )
+    .await?;
+
+    info!(
+        "Successfully uploaded file in {part_count} parts: {} ({} bytes)",
+        item.relative_path, file_size
+    );
+    Ok(true)
 }
 
-// ── Single file upload ──────────────────────────────────────────────────
+fn upload_timed_out(item: &UploadItem, upload_timeout: Duration) -> UploadError {
+    UploadError::network(format!(
+        "Upload of '{}' timed out after {}s",
+        item.relative_path,
+        upload_timeout.as_secs()
+    ))
+}
 
-/// PUT the file content to the presigned URL. This is the only part that
-/// should be held under the concurrency semaphore.
-async fn upload_file_put(
+/// Upload the whole file in one PUT, with the configured integrity header and timeout.
+#[allow(clippy::too_many_arguments)]
+async fn upload_file_single(
     item: &UploadItem,
-    file_content: Bytes,
+    file_content: Option<Bytes>,
     content_type: &str,
+    content_encoding: Option<&str>,
+    expected_crc32c: Option<&str>,
     upload_url: &str,
+    verify_url: Option<&str>,
+    config: &UploadConfig,
     client: &SharedHttpClient,
     app_handle: &AppHandle,
-) -> Result<(), String> {
+) -> Result<(), UploadError> {
+    let integrity_header = integrity_header_for(
+        config.integrity_header,
+        expected_crc32c,
+        file_content.as_ref(),
+        item,
+    )
+    .await?;
+    let upload_timeout = Duration::from_secs(config.upload_timeout_secs);
+    let put_headers = tokio::time::timeout(
+        upload_timeout,
+        upload_file_put(
+            item,
+            file_content,
+            content_type,
+            content_encoding,
+            integrity_header,
+            upload_url,
+            client,
+            app_handle,
+        ),
+    )
+    .await
+    .unwrap_or_else(|_| Err(upload_timed_out(item, upload_timeout)))?;
+
+    match expected_crc32c {
+        Some(expected_crc32c) if config.verify_after_upload => {
+            verify_stored_crc32c(
+                item,
+                expected_crc32c,
+                &put_headers,
+                verify_url,
+                config,
+                client,
+                app_handle,
+            )
+            .await
+        }
+        _ => Ok(()),
+    }
+}
+
+// ── Resumable upload ────────────────────────────────────────────────────
+
+/// Ask the server to open a resumable upload session. Returns None if the server has no
+/// resumable upload support.
+async fn create_resumable_session(
+    file_id: &str,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<Option<String>, UploadError> {
+    let token = get_auth_token(app_handle)?;
+    let session_url = format!(
+        "{}/api/sync/{}/resumable-upload",
+        config.server_url, file_id
+    );
+
+    let mut request = client
+        .post(&session_url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers());
+    if let Some(token_str) = &token {
+        request = request.header("Authorization", format!("Bearer {token_str}"));
+    }
+
+    let response = request.send().await.map_err(|e| {
+        UploadError::network(format!(
+            "Failed to request resumable upload session for file ID '{file_id}': {e}"
+        ))
+    })?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = check_upload_response(response, "Resumable session request").await?;
+
+    let session: ResumableSessionResponse = response.json().await.map_err(|e| {
+        UploadError::server(format!("Failed to parse resumable session response: {e}"))
+    })?;
+    Ok(Some(session.session_url))
+}
+
+enum ResumeOffset {
+    Complete,
+    At(u64),
+}
+
+/// Ask the storage backend how much of the file a session has already received.
+async fn query_resumable_offset(
+    session_url: &str,
+    file_size: u64,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+) -> Result<ResumeOffset, String> {
+    let response = client
+        .put(session_url)
+        .timeout(config.request_timeout())
+        .header(CONTENT_LENGTH, 0)
+        .header(CONTENT_RANGE, format!("bytes */{file_size}"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to query resumable upload offset: {e}"))?;
+
+    if response.status() == StatusCode::PERMANENT_REDIRECT {
+        // "Resume incomplete": Range is the inclusive range received so far, if any
+        let offset = response
+            .headers()
+            .get(RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| range.rsplit('-').next())
+            .and_then(|last| last.parse::<u64>().ok())
+            .map_or(0, |last| last + 1);
+        return Ok(ResumeOffset::At(offset));
+    }
+    check_response(response, "Resumable offset query").await?;
+    Ok(ResumeOffset::Complete)
+}
+
+/// Upload through a resumable session, continuing from the offset the backend confirms when a
+/// session for the same file content survives from an earlier attempt. Returns false, having
+/// uploaded nothing, if the server doesn't support resumable uploads.
+async fn upload_file_resumable(
+    item: &UploadItem,
+    file_id: &str,
+    file_content: Option<Bytes>,
+    fingerprint: Option<FileFingerprint>,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<bool, UploadError> {
+    let Some(sessions) = app_handle.try_state::<ResumableSessionsState>() else {
+        return Ok(false);
+    };
+    let file_size = match &file_content {
+        Some(content) => content.len() as u64,
+        None => tokio::fs::metadata(&item.path)
+            .await
+            .map_err(|e| format!("Failed to stat file '{}': {}", item.relative_path, e))?
+            .len(),
+    };
+
+    // A session is only reused for the same content under the same remote key
+    let existing = sessions.lock().get(&item.path).cloned().filter(|session| {
+        session.remote_key == item.remote_key && session.fingerprint == fingerprint
+    });
+    let mut resumed = None;
+    if let Some(mut session) = existing {
+        match query_resumable_offset(&session.session_url, file_size, config, client).await {
+            Ok(ResumeOffset::Complete) => {
+                sessions.lock().remove(&item.path);
+                info!(
+                    "Resumable upload of '{}' had already completed",
+                    item.relative_path
+                );
+                return Ok(true);
+            }
+            Ok(ResumeOffset::At(offset)) => {
+                session.attempts += 1;
+                info!(
+                    "Resuming upload of '{}' at {offset} of {file_size} bytes (attempt {})",
+                    item.relative_path, session.attempts
+                );
+                resumed = Some((session, offset));
+            }
+            Err(e) => {
+                warn!(
+                    "Can't resume upload of '{}', starting over: {e}",
+                    item.relative_path
+                );
+            }
+        }
+    }
+
+    let (session, offset) = match resumed {
+        Some(resumed) => resumed,
+        None => {
+            let Some(session_url) =
+                create_resumable_session(file_id, config, client, app_handle).await?
+            else {
+                return Ok(false);
+            };
+            let session = ResumableSession {
+                session_url,
+                remote_key: item.remote_key.clone(),
+                fingerprint,
+                attempts: 1,
+            };
+            (session, 0)
+        }
+    };
+    let session_url = session.session_url.clone();
+    sessions.lock().insert(item.path.clone(), session);
+
     info!(
-        "Starting upload for file: {} (attempt: {})",
+        "Starting resumable upload for file: {} (attempt: {})",
         item.relative_path,
         item.retry_count + 1
     );
-
     emit_file_upload_status(&item.relative_path, STATUS_UPLOADING, None, app_handle);
 
-    let file_size = file_content.len();
-    debug!(
-        "Uploading {} bytes for file: {}",
-        file_size, item.relative_path
-    );
+    let remaining = file_size - offset.min(file_size);
+    let chunks = part_body(item, file_content.as_ref(), offset, remaining).await?;
+    let body = Body::wrap_stream(with_upload_progress(
+        chunks,
+        item.relative_path.clone(),
+        offset,
+        file_size,
+        app_handle.clone(),
+    ));
+    let content_range = if remaining == 0 {
+        format!("bytes */{file_size}")
+    } else {
+        format!("bytes {offset}-{}/{file_size}", file_size - 1)
+    };
 
     let response = client
-        .put(upload_url)
-        .header("Content-Type", content_type)
-        .body(file_content)
+        .put(&session_url)
+        .header(CONTENT_LENGTH, remaining)
+        .header(CONTENT_RANGE, content_range)
+        .body(body)
         .send()
         .await
         .map_err(|e| {
-            format!(
-                "Failed to upload file '{}' to presigned URL: {}",
+            UploadError::network(format!(
+                "Failed to upload file '{}': {}",
                 item.relative_path, e
-            )
+            ))
         })?;
+    check_upload_response(
+        response,
+        &format!("Resumable upload for '{}'", item.relative_path),
+    )
+    .await?;
 
-    check_response(response, &format!("Upload for '{}'", item.relative_path)).await?;
-
+    sessions.lock().remove(&item.path);
     info!(
-        "Successfully uploaded file: {} ({} bytes)",
+        "Successfully uploaded file: {} ({} bytes, resumed at {offset})",
         item.relative_path, file_size
     );
+    Ok(true)
+}
+
+/// Re-hash a file from disk and check it still matches the hash sent in the batch request.
+/// Returns false if the file changed or couldn't be read.
+async fn verify_file_unchanged(item: &UploadItem, expected_crc32c: Option<&str>) -> bool {
+    let hash = match compute_crc32c_hash_streaming(&item.path).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            warn!("Failed to re-read '{}' for verification: {e}", item.relative_path);
+            return false;
+        }
+    };
+    if expected_crc32c != Some(hash.as_str()) {
+        warn!(
+            "File '{}' changed since it was hashed, re-queuing for a fresh presigned URL",
+            item.relative_path
+        );
+        return false;
+    }
+    true
+}
+
+/// Check a file's size and mtime still match what was recorded when it was hashed.
+fn fingerprint_unchanged(item: &UploadItem, fingerprint: Option<FileFingerprint>) -> bool {
+    let current = std::fs::metadata(&item.path)
+        .ok()
+        .map(|metadata| FileFingerprint::from_metadata(&metadata));
+    if current.is_none() || current != fingerprint {
+        warn!(
+            "File '{}' changed since it was hashed, re-queuing for a fresh presigned URL",
+            item.relative_path
+        );
+        return false;
+    }
+    true
+}
+
+/// Drop an in-flight item whose file was deleted before it could be uploaded. This isn't a
+/// failure, so nothing is retried or recorded.
+fn drop_deleted_item(
+    item: &UploadItem,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    app_handle: &AppHandle,
+) {
+    info!(
+        "File '{}' was deleted before it could be uploaded, dropping it",
+        item.relative_path
+    );
+    {
+        let mut progress = upload_progress.lock();
+        progress.in_flight = progress.in_flight.saturating_sub(1);
+    }
+    forget_resumable_session(&item.path, app_handle);
+    emit_file_upload_status(&item.relative_path, STATUS_DELETED, None, app_handle);
+    emit_progress(upload_progress, upload_queue, app_handle);
+}
+
+/// Put an in-flight item back on the queue so it goes through a new batch request,
+/// without counting it as a failed attempt.
+fn requeue_for_presign(
+    mut item: UploadItem,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    app_handle: &AppHandle,
+) {
+    item.timestamp = now_millis();
+    let relative_path = item.relative_path.clone();
+    upload_queue.lock().push_back(item);
+    {
+        let mut progress = upload_progress.lock();
+        progress.in_flight = progress.in_flight.saturating_sub(1);
+    }
+    emit_file_upload_status(&relative_path, STATUS_QUEUED, None, app_handle);
+    emit_progress(upload_progress, upload_queue, app_handle);
+}
+
+async fn update_file_metadata(
+    file_id: &str,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let token = get_auth_token(app_handle)?;
+    let metadata_url = format!("{}/api/sync/{}/confirm-upload", config.server_url, file_id);
+
+    debug!("Sending metadata update request to: {metadata_url}");
+
+    let build_request = |token: Option<&str>| {
+        let request = client
+            .post(&metadata_url)
+            .timeout(config.request_timeout())
+            .headers(config.server_headers());
+        match token {
+            Some(token_str) => request.header("Authorization", format!("Bearer {token_str}")),
+            None => request,
+        }
+    };
+
+    let (response, _) = send_with_token_refresh(token, build_request, client, app_handle)
+        .await
+        .map_err(|e| format!("Failed to send metadata update for file ID '{file_id}': {e}"))?;
+
+    check_response(
+        response,
+        &format!("Metadata update for file ID '{file_id}'"),
+    )
+    .await?;
+
+    info!("Successfully updated metadata for file ID: {file_id}");
+    Ok(())
+}
+
+struct PendingMetadata {
+    file_id: String,
+    item: UploadItem,
+    fingerprint: Option<FileFingerprint>,
+}
+
+/// Uploaded files waiting for their metadata update, sent together by
+/// `flush_metadata_updates` rather than one request per file.
+#[derive(Default)]
+struct MetadataBatch {
+    pending: Vec<PendingMetadata>,
+    last_flush: Option<Instant>,
+    /// The server has no batch endpoint (older versions), so updates go one file at a time.
+    batch_unsupported: bool,
+}
+
+type MetadataBatchState = Arc<Mutex<MetadataBatch>>;
+
+enum MetadataBatchError {
+    Unsupported,
+    Failed(String),
+}
+
+async fn update_file_metadata_batch(
+    file_ids: Vec<String>,
+    config: &UploadConfig,
+    client: &SharedHttpClient,
+    app_handle: &AppHandle,
+) -> Result<(), MetadataBatchError> {
+    let token = get_auth_token(app_handle).map_err(MetadataBatchError::Failed)?;
+    let batch_url = format!("{}/api/sync/update_metadata_batch", config.server_url);
+    let file_count = file_ids.len();
+
+    let body = MetadataBatchBody { file_ids };
+    let build_request = |token: Option<&str>| {
+        let request = client
+            .post(&batch_url)
+            .timeout(config.request_timeout())
+            .headers(config.server_headers())
+            .json(&body);
+        match token {
+            Some(token_str) => request.header("Authorization", format!("Bearer {token_str}")),
+            None => request,
+        }
+    };
+
+    let (response, _) = send_with_token_refresh(token, build_request, client, app_handle)
+        .await
+        .map_err(|e| {
+            MetadataBatchError::Failed(format!("Failed to send batch metadata update: {e}"))
+        })?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(MetadataBatchError::Unsupported);
+    }
+    check_response(response, "Batch metadata update")
+        .await
+        .map_err(MetadataBatchError::Failed)?;
 
+    info!("Successfully updated metadata for {file_count} files");
     Ok(())
 }
 
-async fn update_file_metadata(
-    file_id: &str,
-    config: &UploadConfig,
-    client: &SharedHttpClient,
-    app_handle: &AppHandle,
-) -> Result<(), String> {
-    let token = get_auth_token(app_handle)?;
-    let metadata_url = format!("{}/api/sync/{}/confirm-upload", config.server_url, file_id);
+fn on_metadata_updated(pending: PendingMetadata, config: &UploadConfig, app_handle: &AppHandle) {
+    if config.delete_after_upload {
+        schedule_local_delete(pending.item, pending.fingerprint, config, app_handle);
+    }
+}
 
-    debug!("Sending metadata update request to: {metadata_url}");
+/// Send all pending metadata updates in one request, falling back to per-file requests
+/// (under the metadata semaphore) if the server doesn't support batching. As before,
+/// a failed metadata update is only logged and never fails the upload.
+async fn flush_metadata_updates(
+    metadata_batch: MetadataBatchState,
+    metadata_semaphore: Arc<Semaphore>,
+    config: UploadConfig,
+    client: SharedHttpClient,
+    app_handle: AppHandle,
+) {
+    let (pending, batch_unsupported) = {
+        let mut batch = metadata_batch.lock();
+        batch.last_flush = Some(Instant::now());
+        (std::mem::take(&mut batch.pending), batch.batch_unsupported)
+    };
+    if pending.is_empty() {
+        return;
+    }
 
-    let mut request = client.post(&metadata_url);
-    if let Some(token_str) = &token {
-        request = request.header("Authorization", format!("Bearer {token_str}"));
+    if !batch_unsupported {
+        let file_ids = pending.iter().map(|p| p.file_id.clone()).collect();
+        match update_file_metadata_batch(file_ids, &config, &client, &app_handle).await {
+            Ok(()) => {
+                for pending in pending {
+                    on_metadata_updated(pending, &config, &app_handle);
+                }
+                return;
+            }
+            Err(MetadataBatchError::Unsupported) => {
+                info!("Server has no batch metadata endpoint, updating files individually");
+                metadata_batch.lock().batch_unsupported = true;
+            }
+            Err(MetadataBatchError::Failed(e)) => {
+                warn!("Failed to update metadata for {} files: {e}", pending.len());
+                return;
+            }
+        }
     }
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send metadata update for file ID '{file_id}': {e}"))?;
+    stream::iter(pending)
+        .for_each_concurrent(None, |pending| {
+            let metadata_semaphore = metadata_semaphore.clone();
+            let config = &config;
+            let client = &client;
+            let app_handle = &app_handle;
+            async move {
+                let result = {
+                    let _permit = metadata_semaphore.acquire_owned().await.unwrap();
+                    update_file_metadata(&pending.file_id, config, client, app_handle).await
+                };
+                match result {
+                    Ok(()) => on_metadata_updated(pending, config, app_handle),
+                    Err(e) => warn!(
+                        "Failed to update metadata for '{}' (file_id: {}): {}",
+                        pending.item.relative_path, pending.file_id, e
+                    ),
+                }
+            }
+        })
+        .await;
+}
 
-    check_response(
-        response,
-        &format!("Metadata update for file ID '{file_id}'"),
-    )
-    .await?;
+/// CRC32C and size of content uploaded this session, for `dedupe_by_content`.
+type UploadedContentState = Arc<Mutex<HashSet<(String, u64)>>>;
 
-    info!("Successfully updated metadata for file ID: {file_id}");
-    Ok(())
-}
+/// Device + inode of hard-linked files, with the CRC32C last uploaded for them this session,
+/// for `dedupe_hardlinks`. Each is locked while one of its links uploads, so the others
+/// wait and then find it done.
+type UploadedLinksState = Arc<Mutex<HashMap<(u64, u64), Arc<tokio::sync::Mutex<Option<String>>>>>>;
 
 // ── Background queue processor ──────────────────────────────────────────
 
-/// Drain up to MAX_BATCH_SIZE items that have aged past the upload delay.
-/// Items are ordered by timestamp (oldest at front), so we drain from the front.
-fn collect_ready_items(queue: &mut VecDeque<UploadItem>, delay_ms: u64) -> Vec<UploadItem> {
+/// Drain up to `max_batch_size` items that have aged past the upload delay, stopping early
+/// once the batch would hold more than `max_upload_memory_bytes` of file content. At least
+/// one item is always taken so a single oversized file can't stall the queue.
+/// Items are roughly ordered by timestamp (oldest at front), but retries backing off may be
+/// anywhere, so the whole queue is scanned. Ready items are taken round-robin across watched
+/// roots so one busy folder can't starve the others. Within each root, items follow
+/// `upload_order`, which for `Fifo` is plain front-to-back order.
+fn collect_ready_items(queue: &mut VecDeque<UploadItem>, config: &UploadConfig) -> Vec<UploadItem> {
     let now_ms = now_millis();
 
-    let count = queue
-        .iter()
-        .take(MAX_BATCH_SIZE)
-        .take_while(|item| now_ms.saturating_sub(item.timestamp) >= delay_ms)
-        .count();
+    // Indices of ready items per root, in queue order
+    let mut roots: Vec<(&str, VecDeque<usize>)> = Vec::new();
+    let ready_items = queue.iter().enumerate().filter(|(_, item)| {
+        is_item_ready(item, now_ms, config.upload_delay_ms, config.max_debounce_ms)
+    });
+    for (index, item) in ready_items {
+        match roots.iter_mut().find(|(root, _)| *root == item.base_path) {
+            Some((_, indices)) => indices.push_back(index),
+            None => roots.push((&item.base_path, VecDeque::from([index]))),
+        }
+    }
+    // Stable sorts, so equal sizes keep their queue order
+    match config.upload_order {
+        UploadOrder::Fifo => {}
+        UploadOrder::SmallestFirst => {
+            for (_, indices) in roots.iter_mut() {
+                indices
+                    .make_contiguous()
+                    .sort_by_key(|&index| queue[index].file_size);
+            }
+        }
+        UploadOrder::LargestFirst => {
+            for (_, indices) in roots.iter_mut() {
+                indices
+                    .make_contiguous()
+                    .sort_by_key(|&index| std::cmp::Reverse(queue[index].file_size));
+            }
+        }
+    }
+
+    let mut selected_order: Vec<usize> = Vec::new();
+    let mut selected = vec![false; queue.len()];
+    let mut selected_count = 0;
+    let mut batch_bytes: u64 = 0;
+    'fill: while selected_count < config.max_batch_size && roots.iter().any(|(_, i)| !i.is_empty())
+    {
+        for (_, indices) in roots.iter_mut() {
+            let Some(&index) = indices.front() else {
+                continue;
+            };
+            batch_bytes = batch_bytes.saturating_add(queue[index].file_size);
+            if selected_count > 0 && batch_bytes > config.max_upload_memory_bytes {
+                break 'fill;
+            }
+            indices.pop_front();
+            selected[index] = true;
+            selected_order.push(index);
+            selected_count += 1;
+            if selected_count >= config.max_batch_size {
+                break 'fill;
+            }
+        }
+    }
 
-    let ready: Vec<UploadItem> = queue.drain(..count).collect();
+    let mut ready: Vec<UploadItem> = Vec::with_capacity(selected_count);
+    if selected_count > 0 {
+        let mut remaining = VecDeque::with_capacity(queue.len() - selected_count);
+        let mut chosen_items: Vec<Option<UploadItem>> = Vec::with_capacity(queue.len());
+        for (item, chosen) in queue.drain(..).zip(selected) {
+            if chosen {
+                chosen_items.push(Some(item));
+            } else {
+                remaining.push_back(item);
+                chosen_items.push(None);
+            }
+        }
+        // Hand items out in the order they were picked, not queue order
+        ready.extend(selected_order.into_iter().filter_map(|i| chosen_items[i].take()));
+        *queue = remaining;
+    }
 
     if !ready.is_empty() || !queue.is_empty() {
         debug!(
@@ -593,96 +3947,707 @@ fn emit_progress(
     app_handle: &AppHandle,
 ) {
     let mut progress = upload_progress.lock();
-    progress.total_queued = upload_queue.lock().len();
+    let queued_bytes = {
+        let queue = upload_queue.lock();
+        progress.total_queued = queue.len();
+        queue.iter().map(|queued| queued.file_size).sum()
+    };
+    progress.refresh_rates(queued_bytes);
     let _ = app_handle.emit("upload_progress", &*progress);
 }
 
+/// Hold off dispatching until the frontend signals it is ready or the startup delay elapses,
+/// whichever comes first.
+pub async fn wait_for_processor_start(
+    processor_start: &ProcessorStartState,
+    upload_config: &UploadConfigState,
+) {
+    let startup_delay = Duration::from_millis(upload_config.lock().startup_delay_ms);
+    tokio::select! {
+        _ = processor_start.notified() => {
+            info!("Upload processor started by frontend");
+        }
+        _ = sleep(startup_delay) => {
+            info!("Upload processor started after {}ms startup delay", startup_delay.as_millis());
+        }
+    }
+}
+
+/// Tracks a round of uploads from the first ready item until the queue drains again.
+struct SyncCycle {
+    folders: HashSet<String>,
+    uploaded_at_start: usize,
+    failed_at_start: usize,
+}
+
+/// Close the current sync cycle once nothing is queued or in flight, notifying the
+/// completion webhook if one is configured.
+fn finish_sync_cycle_if_idle(
+    sync_cycle: &mut Option<SyncCycle>,
+    config: &UploadConfig,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    http_client: &SharedHttpClient,
+) {
+    if sync_cycle.is_none() || !upload_queue.lock().is_empty() {
+        return;
+    }
+    let (in_flight, uploaded, failed) = {
+        let progress = upload_progress.lock();
+        (progress.in_flight, progress.total_uploaded, progress.total_failed)
+    };
+    if in_flight > 0 {
+        return;
+    }
+    let Some(cycle) = sync_cycle.take() else {
+        return;
+    };
+    let Some(url) = config.completion_webhook_url.clone() else {
+        return;
+    };
+    if config.dry_run {
+        return;
+    }
+    let mut folders: Vec<String> = cycle.folders.into_iter().collect();
+    folders.sort();
+    send_webhook(
+        url,
+        SyncCompletePayload {
+            event: "sync_complete",
+            folders,
+            uploaded: uploaded.saturating_sub(cycle.uploaded_at_start),
+            failed: failed.saturating_sub(cycle.failed_at_start),
+            timestamp: Utc::now().to_rfc3339(),
+        },
+        http_client.clone(),
+    );
+}
+
+/// Drop queued files first queued more than `max_item_age_secs` ago. Runs even while uploads
+/// are disabled or paused, so a stale backlog doesn't build up.
+fn expire_stale_items(
+    config: &UploadConfig,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    app_handle: &AppHandle,
+) {
+    let Some(max_item_age_secs) = config.max_item_age_secs else {
+        return;
+    };
+    let cutoff = now_millis().saturating_sub(max_item_age_secs.saturating_mul(1000));
+    let expired: Vec<UploadItem> = {
+        let mut queue = upload_queue.lock();
+        let (expired, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut *queue)
+            .into_iter()
+            .partition(|item| item.first_seen < cutoff);
+        *queue = kept.into();
+        expired
+    };
+    if expired.is_empty() {
+        return;
+    }
+
+    info!(
+        "Discarding {} queued files older than {max_item_age_secs}s",
+        expired.len()
+    );
+    for item in &expired {
+        forget_resumable_session(&item.path, app_handle);
+        emit_file_upload_status(
+            &item.relative_path,
+            STATUS_EXPIRED,
+            Some(format!("Queued for more than {max_item_age_secs}s")),
+            app_handle,
+        );
+    }
+    emit_progress(upload_progress, upload_queue, app_handle);
+}
+
+/// Files the server left out of a batch response, matched by remote key.
+fn missing_from_batch<'a>(
+    items: impl IntoIterator<Item = &'a UploadItem>,
+    results: &[FileCheckResult],
+) -> Vec<UploadItem> {
+    let returned: HashSet<&str> = results
+        .iter()
+        .map(|result| result.file_name.as_str())
+        .collect();
+    items
+        .into_iter()
+        .filter(|item| !returned.contains(item.remote_key.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Count a failed attempt against `item` and set its backoff. False once it has run out of
+/// attempts.
+fn schedule_retry(item: &mut UploadItem, config: &UploadConfig, now_ms: u64) -> bool {
+    item.retry_count += 1;
+    if item.retry_count >= config.max_retry_count {
+        return false;
+    }
+    let backoff = retry_backoff(
+        Duration::from_secs(config.retry_delay_secs),
+        item.retry_count - 1,
+    );
+    item.not_before = now_ms + backoff.as_millis() as u64;
+    true
+}
+
+/// Put files the server left out of a batch response back on the queue, counting it as a failed
+/// attempt, so they aren't silently lost.
+fn requeue_missing_from_batch(
+    items: Vec<UploadItem>,
+    config: &UploadConfig,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    app_handle: &AppHandle,
+) {
+    let missing_count = items.len();
+    for mut item in items {
+        if schedule_retry(&mut item, config, now_millis()) {
+            warn!(
+                "'{}' was missing from the batch response (attempt {}/{}), re-queuing",
+                item.relative_path, item.retry_count, config.max_retry_count
+            );
+            emit_file_upload_status(&item.relative_path, STATUS_QUEUED, None, app_handle);
+            upload_queue.lock().push_back(item);
+        } else {
+            let message = format!(
+                "'{}' was missing from the batch response after {} attempts",
+                item.relative_path, config.max_retry_count
+            );
+            error!("{message}");
+            record_activity(app_handle, LEVEL_ERROR, message.clone());
+            record_failed_upload(&item, &message, app_handle);
+            emit_file_upload_status(
+                &item.relative_path,
+                STATUS_FAILED,
+                Some(message),
+                app_handle,
+            );
+            upload_progress.lock().total_failed += 1;
+        }
+    }
+    {
+        let mut progress = upload_progress.lock();
+        progress.in_flight = progress.in_flight.saturating_sub(missing_count);
+    }
+    emit_progress(upload_progress, upload_queue, app_handle);
+}
+
+/// Whether a read error may clear up by itself, like a file another process has locked.
+fn is_transient_read_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33)) {
+        return true;
+    }
+    matches!(
+        e.kind(),
+        ErrorKind::PermissionDenied
+            | ErrorKind::ResourceBusy
+            | ErrorKind::WouldBlock
+            | ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+    )
+}
+
+/// Report files that couldn't be read for a batch request. Transient errors are retried like
+/// a failed upload; anything else, or running out of retries, lands in the failed list.
+fn handle_read_failures(
+    failures: Vec<ReadFailure>,
+    config: &UploadConfig,
+    upload_queue: &UploadQueue,
+    upload_progress: &UploadProgressState,
+    app_handle: &AppHandle,
+) {
+    for (mut item, e) in failures {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            drop_deleted_item(&item, upload_queue, upload_progress, app_handle);
+            continue;
+        }
+        {
+            let mut progress = upload_progress.lock();
+            progress.in_flight = progress.in_flight.saturating_sub(1);
+        }
+        let error = format!("Failed to read file: {e}");
+        emit_file_upload_status(
+            &item.relative_path,
+            STATUS_FAILED,
+            Some(error.clone()),
+            app_handle,
+        );
+        item.retry_count += 1;
+        if is_transient_read_error(&e) && item.retry_count < config.max_retry_count {
+            let backoff = retry_backoff(
+                Duration::from_secs(config.retry_delay_secs),
+                item.retry_count - 1,
+            );
+            warn!(
+                "Couldn't read '{}' (attempt {}/{}), re-queuing: {e}",
+                item.relative_path, item.retry_count, config.max_retry_count
+            );
+            item.not_before = now_millis() + backoff.as_millis() as u64;
+            upload_queue.lock().push_back(item);
+        } else {
+            let message = format!("Couldn't read '{}': {e}", item.relative_path);
+            error!("{message}");
+            record_activity(app_handle, LEVEL_ERROR, message);
+            record_failed_upload(&item, &error, app_handle);
+            upload_progress.lock().total_failed += 1;
+        }
+    }
+    emit_progress(upload_progress, upload_queue, app_handle);
+}
+
+/// Files drained from the queue together, read, and sent for presigned URLs in one request.
+struct PresignedBatch {
+    prepared: Vec<(PreparedUpload, FileCheckItem)>,
+    read_failures: Vec<ReadFailure>,
+    /// None in dry-run mode, where no URLs are requested
+    batch_results: Option<Result<Vec<FileCheckResult>, String>>,
+}
+
+/// A batch whose presign task is running, with what the processor keeps of it meanwhile.
+struct PendingBatch {
+    /// Copy of the drained items, put back if the task panics or is aborted
+    items: Vec<UploadItem>,
+    /// Each item's registration with `InFlightUploadsState`, by absolute path, so it can be
+    /// cancelled before its upload starts. Taken up by the upload task.
+    cancellations: HashMap<String, (InFlightGuard, AbortRegistration)>,
+    presign: JoinHandle<PresignedBatch>,
+}
+
+impl PendingBatch {
+    fn is_cancelled(&self, item: &UploadItem) -> bool {
+        self.cancellations
+            .get(&item.path)
+            .is_some_and(|(_, registration)| registration.handle().is_aborted())
+    }
+}
+
+/// Take files cancelled while their batch was presigned out of it. `cancel_upload` has already
+/// taken them out of `in_flight`.
+fn drop_cancelled(batch: &mut PresignedBatch, pending: &PendingBatch) {
+    let cancelled_keys: HashSet<&str> = pending
+        .items
+        .iter()
+        .filter(|item| pending.is_cancelled(item))
+        .map(|item| item.remote_key.as_str())
+        .collect();
+    if cancelled_keys.is_empty() {
+        return;
+    }
+    batch
+        .prepared
+        .retain(|(upload, _)| !pending.is_cancelled(&upload.item));
+    batch
+        .read_failures
+        .retain(|(item, _)| !pending.is_cancelled(item));
+    if let Some(Ok(results)) = &mut batch.batch_results {
+        results.retain(|result| !cancelled_keys.contains(result.file_name.as_str()));
+    }
+}
+
+/// Emitted as `batch_started` when files are about to be read and presigned.
+#[derive(Clone, Serialize)]
+struct BatchStartedEvent {
+    file_count: usize,
+}
+
+/// Emitted as `batch_completed` when the presigned batch request returns. `errors` counts the
+/// files that got neither result: unreadable, left out of the response, or in a failed request.
+#[derive(Clone, Serialize)]
+struct BatchCompletedEvent {
+    exists: usize,
+    needs_upload: usize,
+    errors: usize,
+}
+
+/// Read a batch of files and request their presigned URLs. Runs as its own task so up to
+/// `max_in_flight_batches` of these overlap with the uploads of earlier batches.
+async fn presign_batch(
+    ready_items: Vec<UploadItem>,
+    config: UploadConfig,
+    session_context: SessionContext,
+    http_client: SharedHttpClient,
+    app_handle: AppHandle,
+) -> PresignedBatch {
+    let file_count = ready_items.len();
+    if !config.dry_run {
+        let _ = app_handle.emit("batch_started", BatchStartedEvent { file_count });
+    }
+    let (prepared, read_failures) = if config.detect_hard_links {
+        prepare_batch_items_sharing_links(ready_items, &config).await
+    } else {
+        prepare_batch_items(ready_items, &config).await
+    };
+    let batch_results = if config.dry_run {
+        None
+    } else {
+        let results = get_presigned_urls_batch(
+            &prepared,
+            &config,
+            &session_context,
+            &http_client,
+            &app_handle,
+        )
+        .await;
+        let count_status = |status: &str| {
+            results.as_ref().map_or(0, |results| {
+                results
+                    .iter()
+                    .filter(|result| result.status == status)
+                    .count()
+            })
+        };
+        let exists = count_status(STATUS_EXISTS);
+        let needs_upload = count_status(STATUS_NEEDS_UPLOAD);
+        let _ = app_handle.emit(
+            "batch_completed",
+            BatchCompletedEvent {
+                exists,
+                needs_upload,
+                errors: file_count.saturating_sub(exists + needs_upload),
+            },
+        );
+        Some(results)
+    };
+    PresignedBatch {
+        prepared,
+        read_failures,
+        batch_results,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn process_upload_queue(
     upload_queue: UploadQueue,
     upload_config: UploadConfigState,
     upload_progress: UploadProgressState,
+    upload_paused: UploadPausedState,
+    in_flight_uploads: InFlightUploadsState,
     session_context_state: SessionContextState,
+    synced_files: SyncedFilesState,
     http_client: SharedHttpClient,
     app_handle: AppHandle,
 ) {
     let mut semaphore = Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_UPLOADS));
     let mut last_max_concurrent = DEFAULT_MAX_CONCURRENT_UPLOADS;
+    let mut metadata_semaphore = Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_METADATA_UPDATES));
+    let mut last_max_metadata = DEFAULT_MAX_CONCURRENT_METADATA_UPDATES;
+    let mut sync_cycle: Option<SyncCycle> = None;
+    let mut on_battery = false;
+    let mut last_power_check: Option<Instant> = None;
+    let metadata_batch: MetadataBatchState = Arc::default();
+    let uploaded_content: UploadedContentState = Arc::default();
+    let uploaded_links: UploadedLinksState = Arc::default();
+    let multipart_unsupported = Arc::new(AtomicBool::new(false));
+    let resumable_unsupported = Arc::new(AtomicBool::new(false));
+    // Oldest first, so batches are uploaded in the order they were drained
+    let mut presigns: VecDeque<PendingBatch> = VecDeque::new();
 
     loop {
         let config = upload_config.lock().clone();
 
+        let flush_due = {
+            let batch = metadata_batch.lock();
+            !batch.pending.is_empty()
+                && (batch.pending.len() >= MAX_METADATA_BATCH_SIZE
+                    || batch
+                        .last_flush
+                        .is_none_or(|flushed| flushed.elapsed() >= METADATA_FLUSH_INTERVAL))
+        };
+        if flush_due {
+            tauri::async_runtime::spawn(flush_metadata_updates(
+                metadata_batch.clone(),
+                metadata_semaphore.clone(),
+                config.clone(),
+                http_client.clone(),
+                app_handle.clone(),
+            ));
+        }
+
+        expire_stale_items(&config, &upload_queue, &upload_progress, &app_handle);
+
         if !config.enabled {
             sleep(DISABLED_CHECK_INTERVAL).await;
             continue;
         }
 
-        // Recreate semaphore if concurrency setting changed
-        if config.max_concurrent_uploads != last_max_concurrent {
-            semaphore = Arc::new(Semaphore::new(config.max_concurrent_uploads));
-            last_max_concurrent = config.max_concurrent_uploads;
+        if *upload_paused.lock() {
+            sleep(PAUSED_CHECK_INTERVAL).await;
+            continue;
         }
 
-        let ready_items = {
-            let mut queue = upload_queue.lock();
-            collect_ready_items(&mut queue, config.upload_delay_ms)
+        if config.upload_only_on_ac
+            && last_power_check.is_none_or(|checked| checked.elapsed() >= POWER_CHECK_INTERVAL)
+        {
+            on_battery = read_power_state().on_ac_power == Some(false);
+            last_power_check = Some(Instant::now());
+        }
+        let waiting_for_power = config.upload_only_on_ac && on_battery;
+        let power_wait_changed = {
+            let mut progress = upload_progress.lock();
+            let changed = progress.waiting_for_power != waiting_for_power;
+            progress.waiting_for_power = waiting_for_power;
+            changed
         };
-
-        if ready_items.is_empty() {
-            sleep(QUEUE_POLL_INTERVAL).await;
+        if power_wait_changed {
+            if waiting_for_power {
+                info!("Running on battery, holding uploads until AC power returns");
+            } else {
+                info!("No longer waiting for AC power, resuming uploads");
+            }
+            emit_progress(&upload_progress, &upload_queue, &app_handle);
+        }
+        if waiting_for_power {
+            sleep(PAUSED_CHECK_INTERVAL).await;
             continue;
         }
 
-        // Mark all drained items as in-flight immediately
-        let ready_count = ready_items.len();
-        {
+        let next_window = next_upload_window(&config.upload_windows, Local::now());
+        let resumes_at = next_window.map(|at| at.timestamp_millis() as u64);
+        let schedule_changed = {
             let mut progress = upload_progress.lock();
-            progress.in_flight += ready_count;
-            progress.total_queued = upload_queue.lock().len();
-            let _ = app_handle.emit("upload_progress", &*progress);
+            let changed = progress.schedule_resumes_at != resumes_at;
+            progress.schedule_resumes_at = resumes_at;
+            changed
+        };
+        if schedule_changed {
+            match next_window.zip(resumes_at) {
+                Some((next_window, resumes_at)) => {
+                    info!(
+                        "Outside the upload schedule, holding uploads until {}",
+                        next_window.format("%H:%M")
+                    );
+                    let _ = app_handle.emit("schedule_paused", resumes_at);
+                }
+                None => info!("Upload window open, resuming uploads"),
+            }
+            emit_progress(&upload_progress, &upload_queue, &app_handle);
+        }
+        if resumes_at.is_some() {
+            sleep(PAUSED_CHECK_INTERVAL).await;
+            continue;
         }
 
-        // Read files and prepare batch request
-        let prepared = prepare_batch_items(ready_items.clone()).await;
-
-        // Items that failed to read in prepare_batch_items are lost from in_flight
-        let prepared_count = prepared.len();
-        if prepared_count < ready_count {
-            let mut progress = upload_progress.lock();
-            progress.in_flight -= ready_count - prepared_count;
-            let _ = app_handle.emit("upload_progress", &*progress);
+        // Recreate semaphore if concurrency setting changed
+        if config.max_concurrent_uploads != last_max_concurrent {
+            semaphore = Arc::new(Semaphore::new(config.max_concurrent_uploads));
+            last_max_concurrent = config.max_concurrent_uploads;
+        }
+        if config.max_concurrent_metadata_updates != last_max_metadata {
+            metadata_semaphore = Arc::new(Semaphore::new(config.max_concurrent_metadata_updates));
+            last_max_metadata = config.max_concurrent_metadata_updates;
         }
 
-        // Get presigned URLs for the batch (read session context at request time)
-        let session_context = session_context_state.lock().clone();
-        let batch_results =
-            match get_presigned_urls_batch(&prepared, &config, &session_context, &http_client, &app_handle).await {
-                Ok(results) => results,
-                Err(e) => {
-                    error!("Batch presigned request failed: {e}");
-                    {
-                        let mut progress = upload_progress.lock();
-                        progress.in_flight = progress.in_flight.saturating_sub(prepared_count);
-                        let _ = app_handle.emit("upload_progress", &*progress);
+        if presigns.len() < config.max_in_flight_batches {
+            let ready_items = {
+                let mut queue = upload_queue.lock();
+                collect_ready_items(&mut queue, &config)
+            };
+            // Files may have grown past the limit while queued; check before reading any
+            let mut checked_items = Vec::with_capacity(ready_items.len());
+            for item in ready_items {
+                let size = match tokio::fs::metadata(&item.path).await {
+                    Ok(metadata) => metadata.len(),
+                    // Reading it will fail too and be reported there
+                    Err(e) => {
+                        debug!(
+                            "Failed to stat '{}' before reading: {e}",
+                            item.relative_path
+                        );
+                        item.file_size
                     }
-                    {
-                        let mut queue = upload_queue.lock();
-                        for item in ready_items {
-                            queue.push_back(item);
-                        }
+                };
+                // The size limit can be raised or lowered per folder
+                let item_config =
+                    effective_upload_config(&item.base_path, &upload_config, &app_handle);
+                if !skip_if_too_large(&item.relative_path, size, &item_config, &app_handle) {
+                    checked_items.push(item);
+                }
+            }
+            let ready_items = checked_items;
+
+            if !ready_items.is_empty() {
+                let cycle = sync_cycle.get_or_insert_with(|| {
+                    let progress = upload_progress.lock();
+                    SyncCycle {
+                        folders: HashSet::new(),
+                        uploaded_at_start: progress.total_uploaded,
+                        failed_at_start: progress.total_failed,
+                    }
+                });
+                cycle
+                    .folders
+                    .extend(ready_items.iter().map(|item| item.base_path.clone()));
+
+                // Mark all drained items as in-flight immediately
+                {
+                    let mut progress = upload_progress.lock();
+                    progress.in_flight += ready_items.len();
+                    progress.total_queued = upload_queue.lock().len();
+                    let _ = app_handle.emit("upload_progress", &*progress);
+                }
+
+                // Cancellable from now on, not just once their uploads start
+                let cancellations = ready_items
+                    .iter()
+                    .map(|item| {
+                        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+                        let guard = InFlightGuard::register(&in_flight_uploads, item, abort_handle);
+                        (item.path.clone(), (guard, abort_registration))
+                    })
+                    .collect();
+
+                // Read session context at request time
+                let session_context = session_context_state.lock().clone();
+                let items = ready_items.clone();
+                let presign = tauri::async_runtime::spawn(presign_batch(
+                    ready_items,
+                    config.clone(),
+                    session_context,
+                    http_client.clone(),
+                    app_handle.clone(),
+                ));
+                presigns.push_back(PendingBatch {
+                    items,
+                    cancellations,
+                    presign,
+                });
+                // Start another batch, if allowed, before waiting on this one
+                continue;
+            }
+        }
+
+        process_pending_deletes(&config, &http_client, &app_handle);
+
+        let Some(mut pending) = presigns.pop_front() else {
+            finish_sync_cycle_if_idle(
+                &mut sync_cycle,
+                &config,
+                &upload_queue,
+                &upload_progress,
+                &http_client,
+            );
+            // Let the throughput and ETA fall as uploads age out, even with none completing
+            let window_expired = upload_progress
+                .lock()
+                .recent_uploads
+                .front()
+                .is_some_and(|(at, _, _)| at.elapsed() > THROUGHPUT_WINDOW);
+            if window_expired {
+                emit_progress(&upload_progress, &upload_queue, &app_handle);
+            }
+            sleep(QUEUE_POLL_INTERVAL).await;
+            continue;
+        };
+        let mut batch = match (&mut pending.presign).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                error!("Batch presigned request task failed: {e}");
+                let items: Vec<UploadItem> = std::mem::take(&mut pending.items)
+                    .into_iter()
+                    .filter(|item| !pending.is_cancelled(item))
+                    .collect();
+                {
+                    let mut progress = upload_progress.lock();
+                    progress.in_flight = progress.in_flight.saturating_sub(items.len());
+                    let _ = app_handle.emit("upload_progress", &*progress);
+                }
+                upload_queue.lock().extend(items);
+                sleep(Duration::from_secs(config.retry_delay_secs)).await;
+                continue;
+            }
+        };
+        drop_cancelled(&mut batch, &pending);
+        let PresignedBatch {
+            prepared,
+            read_failures,
+            batch_results,
+        } = batch;
+        let mut cancellations = pending.cancellations;
+
+        if !read_failures.is_empty() {
+            handle_read_failures(
+                read_failures,
+                &config,
+                &upload_queue,
+                &upload_progress,
+                &app_handle,
+            );
+        }
+        let prepared_count = prepared.len();
+
+        let Some(batch_results) = batch_results else {
+            for (prepared, _) in &prepared {
+                info!("Dry run: would upload '{}'", prepared.item.relative_path);
+                emit_file_upload_status(
+                    &prepared.item.relative_path,
+                    STATUS_DRY_RUN,
+                    None,
+                    &app_handle,
+                );
+            }
+            {
+                let mut progress = upload_progress.lock();
+                progress.total_uploaded += prepared_count;
+                progress.in_flight = progress.in_flight.saturating_sub(prepared_count);
+                let _ = app_handle.emit("upload_progress", &*progress);
+            }
+            sleep(BATCH_PROCESSING_DELAY).await;
+            continue;
+        };
+
+        let batch_results = match batch_results {
+            Ok(results) => results,
+            Err(e) => {
+                error!("Batch presigned request failed: {e}");
+                record_activity(
+                    &app_handle,
+                    LEVEL_ERROR,
+                    format!("Batch presigned request failed: {e}"),
+                );
+                {
+                    let mut progress = upload_progress.lock();
+                    progress.in_flight = progress.in_flight.saturating_sub(prepared_count);
+                    let _ = app_handle.emit("upload_progress", &*progress);
+                }
+                {
+                    let mut queue = upload_queue.lock();
+                    for (upload, _) in prepared {
+                        queue.push_back(upload.item);
                     }
-                    sleep(RETRY_DELAY).await;
-                    continue;
                 }
-            };
+                sleep(Duration::from_secs(config.retry_delay_secs)).await;
+                continue;
+            }
+        };
+
+        let missing = missing_from_batch(
+            prepared.iter().map(|(upload, _)| &upload.item),
+            &batch_results,
+        );
+        if !missing.is_empty() {
+            requeue_missing_from_batch(
+                missing,
+                &config,
+                &upload_queue,
+                &upload_progress,
+                &app_handle,
+            );
+        }
 
         // Process each result
         for result in batch_results {
             // Find the matching prepared upload
             let prepared_upload = prepared
                 .iter()
-                .find(|(upload, _)| upload.item.relative_path == result.file_name);
+                .find(|(upload, _)| upload.item.remote_key == result.file_name);
 
-            let Some((prepared, _)) = prepared_upload else {
+            let Some((prepared, check_item)) = prepared_upload else {
                 warn!("No matching prepared upload for: {}", result.file_name);
                 {
                     let mut progress = upload_progress.lock();
@@ -697,7 +4662,28 @@ pub async fn process_upload_queue(
                     "File '{}' already exists (file_id: {}), skipping upload",
                     prepared.item.relative_path, result.file_id
                 );
-                let _ = app_handle.emit("file_uploaded", &prepared.item.relative_path);
+                record_synced_file(&synced_files, prepared);
+                record_last_sync(&prepared.item, &app_handle);
+                // Only delete on this path when the server's copy has our exact content
+                if config.delete_after_upload
+                    && result.crc32c.is_some()
+                    && result.crc32c == check_item.crc32c
+                {
+                    schedule_local_delete(
+                        prepared.item.clone(),
+                        prepared.fingerprint,
+                        &config,
+                        &app_handle,
+                    );
+                }
+                clear_failed_upload(&prepared.item.path, &app_handle);
+                let _ = app_handle.emit(
+                    "file_uploaded",
+                    FileUploadedEvent {
+                        relative_path: prepared.item.relative_path.clone(),
+                        duration_ms: None,
+                    },
+                );
                 let _ = app_handle.emit("upload_success", &prepared.item.relative_path);
                 emit_file_upload_status(
                     &prepared.item.relative_path,
@@ -715,14 +4701,28 @@ pub async fn process_upload_queue(
             }
 
             if result.status != STATUS_NEEDS_UPLOAD {
+                let error = format!("Server returned status '{}'", result.status);
+                warn!(
+                    "File '{}' can't be uploaded: {error}",
+                    prepared.item.relative_path
+                );
+                record_failed_upload(&prepared.item, &error, &app_handle);
+                emit_file_upload_status(
+                    &prepared.item.relative_path,
+                    STATUS_FAILED,
+                    Some(error),
+                    &app_handle,
+                );
                 {
                     let mut progress = upload_progress.lock();
+                    progress.total_failed += 1;
                     progress.in_flight = progress.in_flight.saturating_sub(1);
                     let _ = app_handle.emit("upload_progress", &*progress);
                 }
                 continue;
             }
 
+            let verify_url = result.verify_url.clone();
             let upload_url = match result.upload_url {
                 Some(url) => url,
                 None => {
@@ -743,54 +4743,252 @@ pub async fn process_upload_queue(
             };
 
             // Spawn concurrent upload task (item is already tracked as in-flight)
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            let mut permit = Some(semaphore.clone().acquire_owned().await.unwrap());
+            let semaphore_clone = semaphore.clone();
+            let multipart_unsupported_clone = multipart_unsupported.clone();
+            let resumable_unsupported_clone = resumable_unsupported.clone();
             let config_clone = config.clone();
             let client_clone = http_client.clone();
             let app_clone = app_handle.clone();
             let queue_clone = upload_queue.clone();
             let progress_clone = upload_progress.clone();
+            let synced_files_clone = synced_files.clone();
+            let metadata_batch_clone = metadata_batch.clone();
+            let uploaded_content_clone = uploaded_content.clone();
+            let uploaded_links_clone = uploaded_links.clone();
             let file_id = result.file_id.clone();
             let mut item = prepared.item.clone();
             let file_content = prepared.file_content.clone();
             let content_type = prepared.content_type.clone();
+            let content_encoding = prepared.content_encoding.clone();
+            let fingerprint = prepared.fingerprint;
+            let expected_crc32c = check_item.crc32c.clone();
+            let body_crc32c = prepared
+                .body_crc32c
+                .clone()
+                .or_else(|| expected_crc32c.clone());
+            let (in_flight_guard, abort_registration) =
+                cancellations.remove(&item.path).unwrap_or_else(|| {
+                    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+                    let guard = InFlightGuard::register(&in_flight_uploads, &item, abort_handle);
+                    (guard, abort_registration)
+                });
+
+            let upload_task = async move {
+                let started = Instant::now();
+                if !tokio::fs::try_exists(&item.path).await.unwrap_or(true) {
+                    drop(permit);
+                    drop_deleted_item(&item, &queue_clone, &progress_clone, &app_clone);
+                    return;
+                }
+                // Make sure the file hasn't changed since it was hashed: cheaply by size and
+                // mtime, or for streamed files, which are read again, by re-hashing when
+                // configured. Content kept from the batch read is what was hashed, but would
+                // still be stale.
+                let unchanged = if config_clone.verify_before_upload && file_content.is_none() {
+                    verify_file_unchanged(&item, expected_crc32c.as_deref()).await
+                } else {
+                    fingerprint_unchanged(&item, fingerprint)
+                };
+                if !unchanged {
+                    drop(permit);
+                    requeue_for_presign(item, &queue_clone, &progress_clone, &app_clone);
+                    return;
+                }
 
-            tauri::async_runtime::spawn(async move {
-                // Upload the file (PUT to presigned URL only)
-                let upload_result = upload_file_put(
-                    &item,
-                    file_content,
-                    &content_type,
-                    &upload_url,
-                    &client_clone,
-                    &app_clone,
-                )
-                .await;
+                let link_identity =
+                    if config_clone.detect_hard_links || config_clone.dedupe_hardlinks {
+                        tokio::fs::metadata(&item.path)
+                            .await
+                            .ok()
+                            .as_ref()
+                            .and_then(hard_link_identity)
+                    } else {
+                        None
+                    };
+                let content_key = (config_clone.dedupe_by_content
+                    || (config_clone.detect_hard_links && link_identity.is_none()))
+                .then(|| {
+                    expected_crc32c
+                        .clone()
+                        .map(|crc32c| (crc32c, item.file_size))
+                })
+                .flatten();
+                let link_slot =
+                    link_identity
+                        .zip(expected_crc32c.clone())
+                        .map(|(identity, crc32c)| {
+                            let slot = uploaded_links_clone
+                                .lock()
+                                .entry(identity)
+                                .or_default()
+                                .clone();
+                            (slot, crc32c)
+                        });
+                let mut link_guard = match &link_slot {
+                    Some((slot, _)) => Some(match slot.clone().try_lock_owned() {
+                        Ok(guard) => guard,
+                        Err(_) => {
+                            // Another link of this file is uploading; wait without holding a
+                            // permit its parts may need
+                            drop(permit.take());
+                            let guard = slot.clone().lock_owned().await;
+                            permit = Some(semaphore_clone.clone().acquire_owned().await.unwrap());
+                            guard
+                        }
+                    }),
+                    None => None,
+                };
+                let link_uploaded = link_guard
+                    .as_ref()
+                    .zip(link_slot.as_ref())
+                    .is_some_and(|(guard, (_, crc32c))| guard.as_deref() == Some(crc32c.as_str()));
+                let already_uploaded = link_uploaded
+                    || content_key
+                        .as_ref()
+                        .is_some_and(|key| uploaded_content_clone.lock().contains(key));
+
+                let use_multipart = config_clone
+                    .multipart_threshold_bytes
+                    .is_some_and(|threshold| item.file_size > threshold)
+                    && !multipart_unsupported_clone.load(Ordering::Relaxed);
+                let use_resumable = config_clone.resumable_uploads
+                    && !resumable_unsupported_clone.load(Ordering::Relaxed);
+
+                // Parts take their own permits, so the file's permit is given up meanwhile
+                let multipart_result = if already_uploaded || !use_multipart {
+                    Ok(false)
+                } else {
+                    drop(permit.take());
+                    let result = upload_file_multipart(
+                        &item,
+                        &file_id,
+                        file_content.clone(),
+                        &config_clone,
+                        &semaphore_clone,
+                        &client_clone,
+                        &app_clone,
+                    )
+                    .await;
+                    if matches!(result, Ok(false)) {
+                        info!("Server doesn't support multipart uploads, using a single PUT");
+                        multipart_unsupported_clone.store(true, Ordering::Relaxed);
+                        permit = Some(semaphore_clone.acquire_owned().await.unwrap());
+                    }
+                    result
+                };
+
+                let upload_timeout = Duration::from_secs(config_clone.upload_timeout_secs);
+                let upload_result = if already_uploaded {
+                    info!(
+                        "Content of '{}' was already uploaded this session, skipping PUT",
+                        item.relative_path
+                    );
+                    Ok(())
+                } else {
+                    match multipart_result {
+                        Ok(true) => Ok(()),
+                        Err(e) => Err(e),
+                        Ok(false) => {
+                            let resumable_result = if use_resumable {
+                                let result = tokio::time::timeout(
+                                    upload_timeout,
+                                    upload_file_resumable(
+                                        &item,
+                                        &file_id,
+                                        file_content.clone(),
+                                        fingerprint,
+                                        &config_clone,
+                                        &client_clone,
+                                        &app_clone,
+                                    ),
+                                )
+                                .await
+                                .unwrap_or_else(|_| {
+                                    Err(upload_timed_out(&item, upload_timeout))
+                                });
+                                if matches!(result, Ok(false)) {
+                                    info!(
+                                        "Server doesn't support resumable uploads, \
+                                         using a single PUT"
+                                    );
+                                    resumable_unsupported_clone.store(true, Ordering::Relaxed);
+                                }
+                                result
+                            } else {
+                                Ok(false)
+                            };
+                            match resumable_result {
+                                Ok(true) => Ok(()),
+                                Err(e) => Err(e),
+                                Ok(false) => {
+                                    upload_file_single(
+                                        &item,
+                                        file_content,
+                                        &content_type,
+                                        content_encoding.as_deref(),
+                                        body_crc32c.as_deref(),
+                                        &upload_url,
+                                        verify_url.as_deref(),
+                                        &config_clone,
+                                        &client_clone,
+                                        &app_clone,
+                                    )
+                                    .await
+                                }
+                            }
+                        }
+                    }
+                };
 
-                // Release the permit immediately after PUT so the next upload can start
+                // Release the permit immediately after PUT so the next upload can start.
+                // Past this point the upload can no longer be cancelled.
                 drop(permit);
+                drop(in_flight_guard);
+                let duration = started.elapsed();
 
                 match upload_result {
                     Ok(()) => {
-                        // Metadata update runs outside the semaphore — doesn't block other uploads
-                        if let Err(e) = update_file_metadata(
-                            &file_id,
-                            &config_clone,
-                            &client_clone,
-                            &app_clone,
-                        )
-                        .await
-                        {
-                            warn!(
-                                "Failed to update metadata for '{}' (file_id: {}): {}",
-                                item.relative_path, file_id, e
-                            );
+                        if let Some(content_key) = content_key {
+                            uploaded_content_clone.lock().insert(content_key);
+                        }
+                        if let Some((guard, (_, crc32c))) = link_guard.as_mut().zip(link_slot) {
+                            **guard = Some(crc32c);
                         }
+                        drop(link_guard);
+                        // Metadata is updated in batches by the processor loop
+                        metadata_batch_clone.lock().pending.push(PendingMetadata {
+                            file_id: file_id.clone(),
+                            item: item.clone(),
+                            fingerprint,
+                        });
+
+                        if let Some(fingerprint) = fingerprint {
+                            synced_files_clone.lock().insert(item.path.clone(), fingerprint);
+                        }
+                        record_last_sync(&item, &app_clone);
+                        clear_failed_upload(&item.path, &app_clone);
 
                         debug!(
                             "Upload completed for: {} (file_id: {})",
                             item.relative_path, file_id
                         );
-                        let _ = app_clone.emit("file_uploaded", &item.relative_path);
+                        audit_upload(
+                            &config_clone,
+                            AUDIT_EVENT_UPLOADED,
+                            STATUS_UPLOADED,
+                            &item,
+                            None,
+                            duration,
+                            &app_clone,
+                        );
+                        let _ = app_clone.emit(
+                            "file_uploaded",
+                            FileUploadedEvent {
+                                relative_path: item.relative_path.clone(),
+                                duration_ms: Some(duration.as_millis() as u64),
+                            },
+                        );
                         let _ = app_clone.emit("upload_success", &item.relative_path);
                         emit_file_upload_status(
                             &item.relative_path,
@@ -802,34 +5000,95 @@ pub async fn process_upload_queue(
                             let mut progress = progress_clone.lock();
                             progress.total_uploaded += 1;
                             progress.in_flight = progress.in_flight.saturating_sub(1);
-                            progress.total_queued = queue_clone.lock().len();
+                            let queued_bytes = {
+                                let queue = queue_clone.lock();
+                                progress.total_queued = queue.len();
+                                queue.iter().map(|queued| queued.file_size).sum()
+                            };
+                            progress.record_upload(item.file_size, queued_bytes, duration);
                             let _ = app_clone.emit("upload_progress", &*progress);
                         }
                     }
+                    // Not the file's fault, so it gets a fresh URL without using up a retry
+                    Err(e)
+                        if e.category == UploadErrorCategory::UrlExpired
+                            && item.expiry_requeues < MAX_EXPIRY_REQUEUES =>
+                    {
+                        info!(
+                            "Presigned URL for '{}' expired before upload, re-queuing for a new one",
+                            item.relative_path
+                        );
+                        item.expiry_requeues += 1;
+                        requeue_for_presign(item, &queue_clone, &progress_clone, &app_clone);
+                    }
                     Err(e) => {
                         item.retry_count += 1;
                         {
                             let mut progress = progress_clone.lock();
                             progress.in_flight = progress.in_flight.saturating_sub(1);
                         }
-                        if item.retry_count < MAX_RETRY_COUNT {
-                            warn!(
-                                "Upload failed for '{}' (attempt {}/{}), will retry: {}",
-                                item.relative_path, item.retry_count, MAX_RETRY_COUNT, e
+                        let max_retry_count = config_clone.max_retry_count;
+                        if item.retry_count < max_retry_count {
+                            let backoff = retry_backoff(
+                                Duration::from_secs(config_clone.retry_delay_secs),
+                                item.retry_count - 1,
+                            );
+                            let message = format!(
+                                "Upload failed for '{}' (attempt {}/{}), will retry in {:.1}s: {}",
+                                item.relative_path,
+                                item.retry_count,
+                                max_retry_count,
+                                backoff.as_secs_f64(),
+                                e
+                            );
+                            warn!("{message}");
+                            record_activity(&app_clone, LEVEL_WARN, message);
+                            audit_upload(
+                                &config_clone,
+                                AUDIT_EVENT_RETRYING,
+                                STATUS_QUEUED,
+                                &item,
+                                Some(&e.message),
+                                duration,
+                                &app_clone,
                             );
-                            item.timestamp = now_millis();
+                            item.not_before = now_millis() + backoff.as_millis() as u64;
                             queue_clone.lock().push_back(item);
                         } else {
-                            error!(
+                            let message = format!(
                                 "Upload permanently failed for '{}' after {} attempts: {}",
-                                item.relative_path, MAX_RETRY_COUNT, e
+                                item.relative_path, max_retry_count, e
+                            );
+                            error!("{message}");
+                            record_activity(&app_clone, LEVEL_ERROR, message);
+                            audit_upload(
+                                &config_clone,
+                                AUDIT_EVENT_FAILED,
+                                STATUS_FAILED,
+                                &item,
+                                Some(&e.message),
+                                duration,
+                                &app_clone,
                             );
-                            let _ =
-                                app_clone.emit("upload_failed", (&item.relative_path, e.clone()));
+                            let _ = app_clone.emit("upload_failed", (&item.relative_path, &e));
+                            record_failed_upload(&item, &e.message, &app_clone);
+                            if let Some(url) = config_clone.failure_webhook_url.clone() {
+                                send_webhook(
+                                    url,
+                                    UploadFailedPayload {
+                                        event: "upload_failed",
+                                        folder: item.base_path.clone(),
+                                        relative_path: item.relative_path.clone(),
+                                        error: e.message.clone(),
+                                        timestamp: Utc::now().to_rfc3339(),
+                                    },
+                                    client_clone.clone(),
+                                );
+                            }
                             emit_file_upload_status(
                                 &item.relative_path,
                                 STATUS_FAILED,
-                                Some(e),
+                                Some(e.message),
                                 &app_clone,
                             );
                             {
@@ -844,19 +5103,47 @@ pub async fn process_upload_queue(
                         }
                     }
                 }
-            });
+            };
+            tauri::async_runtime::spawn(Abortable::new(upload_task, abort_registration));
         }
 
         sleep(BATCH_PROCESSING_DELAY).await;
     }
 }
 
+/// Record an upload attempt's outcome in the audit log, if enabled.
+fn audit_upload(
+    config: &UploadConfig,
+    event: &'static str,
+    status: &'static str,
+    item: &UploadItem,
+    error: Option<&str>,
+    duration: Duration,
+    app_handle: &AppHandle,
+) {
+    if !config.audit_log {
+        return;
+    }
+    write_audit_record(
+        app_handle,
+        &AuditRecord {
+            timestamp: now_millis(),
+            event,
+            relative_path: &item.relative_path,
+            status,
+            error,
+            bytes: item.file_size,
+            duration_ms: duration.as_millis() as u64,
+        },
+    );
+}
+
 // ── Tauri commands ──────────────────────────────────────────────────────
 
 #[tauri::command]
 pub fn get_upload_config(
     upload_config: tauri::State<'_, UploadConfigState>,
-) -> Result<UploadConfig, String> {
+) -> Result<UploadConfig, AppError> {
     Ok(upload_config.lock().clone())
 }
 
@@ -864,26 +5151,226 @@ pub fn get_upload_config(
 pub fn set_upload_config(
     config: UploadConfig,
     upload_config: tauri::State<'_, UploadConfigState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    config.validate()?;
     *upload_config.lock() = config;
     Ok("Upload configuration updated".to_string())
 }
 
+/// Point uploads at another server, checking the URL first.
+pub fn set_server_url(url: String, upload_config: &UploadConfigState) -> Result<(), AppError> {
+    let mut config = upload_config.lock();
+    let mut updated = config.clone();
+    updated.server_url = url;
+    updated.validate()?;
+    *config = updated;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn start_processing(
+    processor_start: tauri::State<'_, ProcessorStartState>,
+) -> Result<String, AppError> {
+    processor_start.notify_one();
+    Ok("Upload processing started".to_string())
+}
+
 #[tauri::command]
 pub fn get_upload_progress(
     upload_progress: tauri::State<'_, UploadProgressState>,
-) -> Result<UploadProgress, String> {
-    Ok(upload_progress.lock().clone())
+    upload_queue: tauri::State<'_, UploadQueue>,
+) -> Result<UploadProgress, AppError> {
+    let mut progress = upload_progress.lock();
+    // The processor only refreshes this as it works, so read it live (e.g. while paused)
+    progress.total_queued = upload_queue.lock().len();
+    Ok(progress.clone())
+}
+
+#[tauri::command]
+pub fn pause_uploads(
+    upload_paused: tauri::State<'_, UploadPausedState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    *upload_paused.lock() = true;
+    info!("Uploads paused");
+    let _ = app_handle.emit("upload_paused_changed", true);
+    Ok("Uploads paused".to_string())
+}
+
+#[tauri::command]
+pub fn resume_uploads(
+    upload_paused: tauri::State<'_, UploadPausedState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    *upload_paused.lock() = false;
+    info!("Uploads resumed");
+    let _ = app_handle.emit("upload_paused_changed", false);
+    Ok("Uploads resumed".to_string())
+}
+
+/// Zero the session counters without touching the queue. Each counter is reset unless
+/// explicitly opted out with `false`.
+#[tauri::command]
+pub fn reset_progress_counters(
+    reset_uploaded: Option<bool>,
+    reset_failed: Option<bool>,
+    upload_progress: tauri::State<'_, UploadProgressState>,
+    app_handle: AppHandle,
+) -> Result<UploadProgress, AppError> {
+    let mut progress = upload_progress.lock();
+    if reset_uploaded.unwrap_or(true) {
+        progress.total_uploaded = 0;
+        progress.bytes_uploaded_total = 0;
+    }
+    if reset_failed.unwrap_or(true) {
+        progress.total_failed = 0;
+    }
+    let _ = app_handle.emit("upload_progress", &*progress);
+    Ok(progress.clone())
+}
+
+#[tauri::command]
+pub fn get_last_sync_times(
+    last_sync: tauri::State<'_, LastSyncState>,
+) -> Result<HashMap<String, u64>, AppError> {
+    Ok(last_sync.lock().clone())
+}
+
+/// Number of queued files per watched root.
+#[tauri::command]
+pub fn get_queued_counts_by_folder(
+    upload_queue: tauri::State<'_, UploadQueue>,
+) -> Result<HashMap<String, usize>, AppError> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for item in upload_queue.lock().iter() {
+        *counts.entry(item.base_path.clone()).or_default() += 1;
+    }
+    Ok(counts)
+}
+
+/// Drop a file from the queue, or from a batch being presigned, and abort its upload if one is
+/// in progress.
+#[tauri::command]
+pub fn cancel_upload(
+    relative_path: String,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    in_flight_uploads: tauri::State<'_, InFlightUploadsState>,
+    upload_progress: tauri::State<'_, UploadProgressState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    let removed_from_queue = {
+        let mut queue = upload_queue.lock();
+        let before = queue.len();
+        queue.retain(|item| {
+            let keep = item.relative_path != relative_path;
+            if !keep {
+                forget_resumable_session(&item.path, &app_handle);
+            }
+            keep
+        });
+        queue.len() < before
+    };
+
+    let aborted = match in_flight_uploads.lock().remove(&relative_path) {
+        Some(upload) => {
+            upload.abort_handle.abort();
+            forget_resumable_session(&upload.path, &app_handle);
+            true
+        }
+        None => false,
+    };
+    if aborted {
+        let mut progress = upload_progress.lock();
+        progress.in_flight = progress.in_flight.saturating_sub(1);
+    }
+
+    if !removed_from_queue && !aborted {
+        return Err(AppError::NotFound {
+            message: format!("No queued or in-flight upload for '{relative_path}'"),
+        });
+    }
+
+    info!("Cancelled upload: {relative_path}");
+    emit_file_upload_status(&relative_path, STATUS_CANCELLED, None, &app_handle);
+    emit_progress(upload_progress.inner(), upload_queue.inner(), &app_handle);
+    Ok(format!("Upload cancelled: {relative_path}"))
+}
+
+#[tauri::command]
+pub fn get_all_file_statuses(
+    file_statuses: tauri::State<'_, FileStatusesState>,
+) -> Result<Vec<FileUploadStatus>, AppError> {
+    let mut statuses: Vec<FileUploadStatus> = file_statuses.lock().values().cloned().collect();
+    statuses.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(statuses)
+}
+
+#[tauri::command]
+pub fn get_failed_uploads(
+    failed_uploads: tauri::State<'_, FailedUploadsState>,
+) -> Result<Vec<FailedUploadInfo>, AppError> {
+    let mut failed: Vec<FailedUploadInfo> = failed_uploads
+        .lock()
+        .values()
+        .map(|failed| FailedUploadInfo {
+            relative_path: failed.item.relative_path.clone(),
+            base_path: failed.item.base_path.clone(),
+            error: failed.error.clone(),
+        })
+        .collect();
+    failed.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(failed)
+}
+
+/// Queue every permanently failed file again with a fresh retry budget. They stay listed as
+/// failed until they upload successfully.
+#[tauri::command]
+pub fn retry_failed_uploads(
+    failed_uploads: tauri::State<'_, FailedUploadsState>,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    upload_progress: tauri::State<'_, UploadProgressState>,
+    app_handle: AppHandle,
+) -> Result<usize, AppError> {
+    let items: Vec<UploadItem> = failed_uploads
+        .lock()
+        .values()
+        .map(|failed| failed.item.clone())
+        .collect();
+
+    let now_ms = now_millis();
+    {
+        let mut queue = upload_queue.lock();
+        for mut item in items.iter().cloned() {
+            queue.retain(|queued| queued.path != item.path);
+            item.retry_count = 0;
+            item.not_before = 0;
+            item.timestamp = now_ms;
+            item.first_seen = now_ms;
+            queue.push_back(item);
+        }
+    }
+    for item in &items {
+        emit_file_upload_status(&item.relative_path, STATUS_QUEUED, None, &app_handle);
+    }
+    emit_progress(upload_progress.inner(), upload_queue.inner(), &app_handle);
+
+    info!("Re-queued {} failed uploads", items.len());
+    Ok(items.len())
 }
 
 #[tauri::command]
-pub fn clear_upload_queue(upload_queue: tauri::State<'_, UploadQueue>) -> Result<String, String> {
-    upload_queue.lock().clear();
+pub fn clear_upload_queue(
+    upload_queue: tauri::State<'_, UploadQueue>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    for item in upload_queue.lock().drain(..) {
+        forget_resumable_session(&item.path, &app_handle);
+    }
     Ok("Upload queue cleared".to_string())
 }
 
 #[tauri::command]
-pub fn get_queue_size(upload_queue: tauri::State<'_, UploadQueue>) -> Result<usize, String> {
+pub fn get_queue_size(upload_queue: tauri::State<'_, UploadQueue>) -> Result<usize, AppError> {
     Ok(upload_queue.lock().len())
 }
 
@@ -894,17 +5381,116 @@ pub async fn trigger_manual_upload(
     upload_queue: tauri::State<'_, UploadQueue>,
     upload_config: tauri::State<'_, UploadConfigState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
-    add_to_upload_queue_sync(
+) -> Result<String, AppError> {
+    add_to_upload_queue_with_event_type(
         file_path.clone(),
         base_path,
         upload_queue.inner(),
         upload_config.inner(),
+        EVENT_TYPE_MANUAL,
         &app_handle,
     );
     Ok(format!("File queued for upload: {file_path}"))
 }
 
+/// Queue a single file even if the ignore or include patterns or `.gitignore` exclude it.
+/// Refused while uploads are disabled.
+#[tauri::command]
+pub async fn force_upload(
+    file_path: String,
+    base_path: String,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    upload_config: tauri::State<'_, UploadConfigState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    if !upload_config.lock().enabled {
+        return Err(AppError::UploadsDisabled);
+    }
+    match add_to_upload_queue_with_event_type(
+        file_path.clone(),
+        base_path,
+        upload_queue.inner(),
+        upload_config.inner(),
+        EVENT_TYPE_FORCED,
+        &app_handle,
+    ) {
+        EnqueueOutcome::Queued => Ok(format!("File queued for upload: {file_path}")),
+        EnqueueOutcome::Directory => Err(format!("{file_path} is a directory").into()),
+        EnqueueOutcome::QueueFull => {
+            Err(format!("Upload queue is full, {file_path} not queued").into())
+        }
+        _ => Err(format!("Couldn't queue {file_path}, see its upload status").into()),
+    }
+}
+
+/// Queue several files and stream their status updates over `on_status` only to this
+/// caller. The global `file_upload_status` events are still emitted as usual.
+#[tauri::command]
+pub async fn trigger_manual_upload_batch(
+    file_paths: Vec<String>,
+    base_path: String,
+    on_status: Channel<FileUploadStatus>,
+    upload_queue: tauri::State<'_, UploadQueue>,
+    upload_config: tauri::State<'_, UploadConfigState>,
+    upload_channels: tauri::State<'_, UploadChannelsState>,
+    app_handle: AppHandle,
+) -> Result<String, AppError> {
+    let channel_id = NEXT_UPLOAD_CHANNEL_ID.fetch_add(1, Ordering::Relaxed);
+    upload_channels.lock().insert(
+        channel_id,
+        UploadChannel {
+            channel: on_status,
+            pending: file_paths
+                .iter()
+                .map(|file_path| get_relative_path(file_path, &base_path))
+                .collect(),
+        },
+    );
+
+    for file_path in &file_paths {
+        let outcome = add_to_upload_queue_with_event_type(
+            file_path.clone(),
+            base_path.clone(),
+            upload_queue.inner(),
+            upload_config.inner(),
+            EVENT_TYPE_MANUAL,
+            &app_handle,
+        );
+        // Files that weren't queued may never get a final status, so stop waiting on them
+        if !matches!(outcome, EnqueueOutcome::Queued) {
+            let relative_path = get_relative_path(file_path, &base_path);
+            let mut channels = upload_channels.lock();
+            if let Some(entry) = channels.get_mut(&channel_id) {
+                entry.pending.remove(&relative_path);
+                if entry.pending.is_empty() {
+                    channels.remove(&channel_id);
+                }
+            }
+        }
+    }
+    Ok(format!("{} files queued for upload", file_paths.len()))
+}
+
+/// Show the relative path and remote key a file would be uploaded under, without queueing it.
+#[tauri::command]
+pub fn preview_remote_key(
+    absolute_path: String,
+    base_path: String,
+    upload_config: tauri::State<'_, UploadConfigState>,
+) -> Result<RemoteKeyPreview, AppError> {
+    let config = upload_config.lock().clone();
+    let relative_path = get_relative_path(&absolute_path, &base_path);
+    let remote_key = compute_remote_key(&relative_path, &config);
+    let truncated = remote_key
+        .as_ref()
+        .is_some_and(|key| *key != relative_path);
+    Ok(RemoteKeyPreview {
+        relative_path,
+        remote_key,
+        truncated,
+    })
+}
+
 // ── Session context commands ───────────────────────────────────────────
 
 const SESSION_CONTEXT_STORE_KEY: &str = "session_context";
@@ -912,7 +5498,7 @@ const SESSION_CONTEXT_STORE_KEY: &str = "session_context";
 #[tauri::command]
 pub fn get_session_context(
     session_context: tauri::State<'_, SessionContextState>,
-) -> Result<SessionContext, String> {
+) -> Result<SessionContext, AppError> {
     Ok(session_context.lock().clone())
 }
 
@@ -921,7 +5507,7 @@ pub fn set_session_context(
     context: SessionContext,
     session_context: tauri::State<'_, SessionContextState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     *session_context.lock() = context.clone();
 
     // Persist to store
@@ -941,7 +5527,7 @@ pub fn set_session_context(
 pub fn clear_session_context(
     session_context: tauri::State<'_, SessionContextState>,
     app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let cleared = SessionContext::default();
     *session_context.lock() = cleared.clone();
 
@@ -995,13 +5581,15 @@ pub async fn get_org_members(
     upload_config: tauri::State<'_, UploadConfigState>,
     http_client: tauri::State<'_, SharedHttpClient>,
     app_handle: AppHandle,
-) -> Result<Vec<OrgMember>, String> {
+) -> Result<Vec<OrgMember>, AppError> {
     let config = upload_config.lock().clone();
-    let token = get_auth_token(&app_handle)?
-        .ok_or_else(|| "Not authenticated".to_string())?;
+    let token = get_auth_token(&app_handle)?.ok_or(AppError::NotAuthenticated)?;
     let url = format!("{}/api/sync/org-members", config.server_url);
 
-    let mut request = http_client.get(&url);
+    let mut request = http_client
+        .get(&url)
+        .timeout(config.request_timeout())
+        .headers(config.server_headers());
     if let Some(ref q) = search {
         if !q.is_empty() {
             request = request.query(&[("search", q.as_str())]);
@@ -1012,9 +5600,18 @@ pub async fn get_org_members(
     let response = request
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch org members: {e}"))?;
+        .map_err(|e| AppError::Network {
+            status: None,
+            message: format!("Failed to fetch org members: {e}"),
+        })?;
 
-    let response = check_response(response, "Get org members").await?;
+    let status = response.status().as_u16();
+    let response = check_response(response, "Get org members")
+        .await
+        .map_err(|message| AppError::Network {
+            status: Some(status),
+            message,
+        })?;
 
     let body: OrgMembersResponse = response
         .json()
@@ -1023,3 +5620,301 @@ pub async fn get_org_members(
 
     Ok(body.members)
 }
+
+// ── Connection test command ────────────────────────────────────────────
+
+/// Short so a dead server fails the test quickly instead of hanging the setup screen.
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize, Debug)]
+pub struct ConnectionTestResult {
+    /// The server answered at all
+    pub reachable: bool,
+    /// There is a stored token and the server didn't reject it. Other failures, such as a
+    /// 500, leave this set and fill in `error`.
+    pub authenticated: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Check that `server_url` is reachable and accepts the stored token, with an authenticated
+/// GET of the org members endpoint.
+#[tauri::command]
+pub async fn test_connection(
+    upload_config: tauri::State<'_, UploadConfigState>,
+    http_client: tauri::State<'_, SharedHttpClient>,
+    app_handle: AppHandle,
+) -> Result<ConnectionTestResult, AppError> {
+    let config = upload_config.lock().clone();
+    let token = get_auth_token(&app_handle)?;
+    let url = format!("{}/api/sync/org-members", config.server_url);
+
+    let mut request = http_client
+        .get(&url)
+        .timeout(CONNECTION_TEST_TIMEOUT)
+        .headers(config.server_headers());
+    if let Some(ref token) = token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+
+    let started = Instant::now();
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Ok(ConnectionTestResult {
+                reachable: false,
+                authenticated: false,
+                latency_ms: None,
+                error: Some(format!("Failed to reach {}: {e}", config.server_url)),
+            });
+        }
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let status = response.status();
+    let rejected = status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+    let authenticated = token.is_some() && !rejected;
+    let error = if token.is_none() {
+        Some("Not authenticated".to_string())
+    } else if rejected {
+        Some(format!("Server rejected the token ({status})"))
+    } else if !status.is_success() {
+        Some(format!("Server returned {status}"))
+    } else {
+        None
+    };
+
+    Ok(ConnectionTestResult {
+        reachable: true,
+        authenticated,
+        latency_ms: Some(latency_ms),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    /// A fresh, empty directory under the system temp dir.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sync-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn queued_item(relative_path: &str, timestamp: u64) -> UploadItem {
+        UploadItem {
+            path: format!("/watched/{relative_path}"),
+            relative_path: relative_path.to_string(),
+            base_path: "/watched".to_string(),
+            remote_key: relative_path.to_string(),
+            timestamp,
+            first_seen: timestamp,
+            retry_count: 0,
+            file_size: 1,
+            not_before: 0,
+            expiry_requeues: 0,
+        }
+    }
+
+    #[test]
+    fn default_ignores_match_at_any_depth() {
+        let ignored = UploadConfig::default().ignored_patterns;
+        for path in [
+            ".git/config",
+            "sub/.git/config",
+            "a/b/.git/objects/pack/x.pack",
+            "node_modules/pkg/index.js",
+            "sub/node_modules/pkg/index.js",
+        ] {
+            assert!(
+                matches_any_pattern(path, &ignored),
+                "{path} should be ignored"
+            );
+        }
+        for path in [
+            "data.csv",
+            "sub/data.csv",
+            "git/config",
+            "sub/node_modules.txt",
+        ] {
+            assert!(
+                !matches_any_pattern(path, &ignored),
+                "{path} should not be ignored"
+            );
+        }
+    }
+
+    #[test]
+    fn slash_free_patterns_match_the_file_name() {
+        let tmp = patterns(&["*.tmp"]);
+        assert!(matches_any_pattern("a.tmp", &tmp));
+        assert!(matches_any_pattern("sub/b.tmp", &tmp));
+        assert!(!matches_any_pattern("sub/c.txt", &tmp));
+    }
+
+    #[test]
+    fn leading_slash_anchors_to_root() {
+        let anchored = patterns(&["/build/**"]);
+        assert!(matches_any_pattern("build/out.bin", &anchored));
+        assert!(!matches_any_pattern("sub/build/out.bin", &anchored));
+    }
+
+    #[test]
+    fn backslash_paths_are_matched_like_slashes() {
+        let ignored = patterns(&[".git/**"]);
+        assert!(matches_any_pattern("sub\\.git\\config", &ignored));
+    }
+
+    #[test]
+    fn future_timestamp_is_ready_now() {
+        let now_ms = 1_000_000;
+        let item = queued_item("a.csv", now_ms + 60_000);
+        assert!(is_item_ready(&item, now_ms, 2_000, None));
+
+        let config = UploadConfig::default();
+        let mut queue = VecDeque::from([queued_item("b.csv", now_millis() + 3_600_000)]);
+        let ready = collect_ready_items(&mut queue, &config);
+        assert_eq!(ready.len(), 1);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn recent_timestamp_waits_for_the_delay() {
+        let now_ms = 1_000_000;
+        let item = queued_item("a.csv", now_ms - 500);
+        assert!(!is_item_ready(&item, now_ms, 2_000, None));
+        assert!(is_item_ready(&item, now_ms + 1_500, 2_000, None));
+    }
+
+    #[test]
+    fn retry_backoff_doubles_with_bounded_jitter() {
+        let retry_delay = Duration::from_secs(5);
+        for (attempt, base_secs) in [(0, 5), (1, 10), (2, 20)] {
+            let base = Duration::from_secs(base_secs);
+            let backoff = retry_backoff(retry_delay, attempt);
+            assert!(backoff >= base, "attempt {attempt}: {backoff:?} < {base:?}");
+            assert!(
+                backoff <= base.mul_f64(1.0 + RETRY_JITTER_FRACTION),
+                "attempt {attempt}: {backoff:?} has too much jitter"
+            );
+        }
+        assert!(
+            retry_backoff(retry_delay, 30) <= MAX_RETRY_DELAY.mul_f64(1.0 + RETRY_JITTER_FRACTION)
+        );
+    }
+
+    #[test]
+    fn partial_batch_response_requeues_the_missing_files() {
+        let response: GetPresignedBatchResponse = serde_json::from_value(serde_json::json!({
+            "success": true,
+            "message": "ok",
+            "files": [
+                {"file_name": "a.csv", "crc32c": null, "status": STATUS_EXISTS, "file_id": "1"},
+                {"file_name": "c.csv", "crc32c": null, "status": STATUS_NEEDS_UPLOAD, "file_id": "3"}
+            ]
+        }))
+        .unwrap();
+        let items = [
+            queued_item("a.csv", 0),
+            queued_item("b.csv", 0),
+            queued_item("c.csv", 0),
+        ];
+        let mut missing = missing_from_batch(&items, &response.files);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].relative_path, "b.csv");
+
+        let config = UploadConfig {
+            max_retry_count: 2,
+            ..UploadConfig::default()
+        };
+        let item = &mut missing[0];
+        assert!(schedule_retry(item, &config, 1_000));
+        assert_eq!(item.retry_count, 1);
+        assert!(item.not_before > 1_000);
+        assert!(!schedule_retry(item, &config, 1_000));
+    }
+
+    #[test]
+    fn content_type_overrides_beat_the_built_in_table() {
+        let overrides = HashMap::from([
+            ("log".to_string(), "text/plain".to_string()),
+            ("json".to_string(), "text/plain".to_string()),
+        ]);
+        assert_eq!(get_content_type("logs/app.log", &overrides), "text/plain");
+        assert_eq!(get_content_type("logs/APP.LOG", &overrides), "text/plain");
+        assert_eq!(get_content_type("data.json", &overrides), "text/plain");
+        assert_eq!(
+            get_content_type("data.json", &HashMap::new()),
+            "application/json"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_checked_against_the_watched_folder() {
+        let dir = scratch_dir("symlinks");
+        let base = dir.join("watched");
+        std::fs::create_dir(&base).unwrap();
+        std::fs::write(dir.join("outside.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.join("outside.txt"), base.join("escape")).unwrap();
+        std::os::unix::fs::symlink(base.join("self"), base.join("self")).unwrap();
+        let base_path = base.to_string_lossy();
+
+        let escape = base.join("escape");
+        assert!(resolves_outside_base(&escape.to_string_lossy(), &base_path));
+        // A link to itself can't be resolved, so it isn't treated as leaving the folder
+        let self_link = base.join("self");
+        assert!(!resolves_outside_base(
+            &self_link.to_string_lossy(),
+            &base_path
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn normalize_path_resolves_dot_segments() {
+        assert_eq!(
+            normalize_path(Path::new("/watched/sub/../a/./b.csv")),
+            PathBuf::from("/watched/a/b.csv")
+        );
+        assert_eq!(
+            normalize_path(Path::new("../outside/b.csv")),
+            PathBuf::from("../outside/b.csv")
+        );
+    }
+
+    #[test]
+    fn relative_path_of_a_deleted_file() {
+        let base = scratch_dir("deleted");
+        std::fs::create_dir(base.join("sub")).unwrap();
+        let base_path = base.to_string_lossy();
+        let gone = base.join("sub").join("gone.csv");
+        assert_eq!(
+            get_relative_path(&gone.to_string_lossy(), &base_path),
+            Path::new("sub").join("gone.csv").to_string_lossy()
+        );
+        let dotted = base.join("sub").join("..").join("gone.csv");
+        assert_eq!(
+            get_relative_path(&dotted.to_string_lossy(), &base_path),
+            "gone.csv"
+        );
+        forget_canonical_base_path(&base_path);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn relative_path_with_backslashes() {
+        assert_eq!(
+            get_relative_path(r"C:\watched\sub\..\data\a.csv", r"C:\watched"),
+            r"data\a.csv"
+        );
+    }
+}
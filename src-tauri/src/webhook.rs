@@ -0,0 +1,62 @@
+use log::{info, warn};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::time::sleep;
+
+use crate::http_client::{check_response, SharedHttpClient};
+
+const WEBHOOK_MAX_ATTEMPTS: u32 = 3;
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sent to `completion_webhook_url` when the queue drains after a round of uploads.
+#[derive(Clone, Serialize)]
+pub struct SyncCompletePayload {
+    pub event: &'static str,
+    pub folders: Vec<String>,
+    pub uploaded: usize,
+    pub failed: usize,
+    pub timestamp: String,
+}
+
+/// Sent to `failure_webhook_url` when a file permanently fails to upload.
+#[derive(Clone, Serialize)]
+pub struct UploadFailedPayload {
+    pub event: &'static str,
+    pub folder: String,
+    pub relative_path: String,
+    pub error: String,
+    pub timestamp: String,
+}
+
+/// POST `payload` to `url` in the background, retrying a few times before giving up.
+/// Webhook failures are only logged; they never affect the upload pipeline.
+pub fn send_webhook<T: Serialize + Send + 'static>(
+    url: String,
+    payload: T,
+    client: SharedHttpClient,
+) {
+    tauri::async_runtime::spawn(async move {
+        for attempt in 1..=WEBHOOK_MAX_ATTEMPTS {
+            let request = client.post(&url).timeout(WEBHOOK_TIMEOUT).json(&payload);
+            let result = match request.send().await {
+                Ok(response) => check_response(response, "Webhook").await.map(|_| ()),
+                Err(e) => Err(format!("Failed to send webhook: {e}")),
+            };
+            match result {
+                Ok(()) => {
+                    info!("Webhook delivered to {url}");
+                    return;
+                }
+                Err(e) => {
+                    warn!(
+                        "Webhook to {url} failed (attempt {attempt}/{WEBHOOK_MAX_ATTEMPTS}): {e}"
+                    );
+                    if attempt < WEBHOOK_MAX_ATTEMPTS {
+                        sleep(WEBHOOK_RETRY_DELAY).await;
+                    }
+                }
+            }
+        }
+    });
+}
@@ -10,6 +10,7 @@ import {
   SheetHeader,
   SheetTitle,
 } from '@/components/ui/sheet'
+import { errorMessage } from '@/lib/utils'
 import {
   Field,
   FieldContent,
@@ -81,7 +82,7 @@ export function NetworkDiagnosticSheet({ open, onOpenChange }: Props) {
       setResult(res)
     } catch (e) {
       if (myId !== requestIdRef.current) return
-      setError(String(e))
+      setError(errorMessage(e))
     } finally {
       if (myId === requestIdRef.current) {
         setIsRunning(false)
@@ -165,16 +165,20 @@ export default function Simple() {
   const logsRef = useRef<HTMLDivElement>(null);
   useEffect(() => {
     // Listen for file change events from Tauri
-    const unlistenFileChange = listen("file_change", (event) => {
-      console.log("file_change", event);
-      const fileChange = event.payload as FileChangeEvent;
+    const unlistenFileChange = listen("file_changes_batch", (event) => {
+      console.log("file_changes_batch", event);
+      // Oldest first, so the most recent change for a path ends up on top
+      const batch = event.payload as FileChangeEvent[];
       setFileChanges((prev) => {
-        // Remove any existing entries for this file path
-        const filteredPrev = prev.filter(
-          (change) => change.path !== fileChange.path
-        );
-        // Add the new change at the beginning (most recent)
-        const updated = [fileChange, ...filteredPrev];
+        const latest = new Map<string, FileChangeEvent>();
+        for (const change of batch) {
+          latest.delete(change.path);
+          latest.set(change.path, change);
+        }
+        // Remove any existing entries for these file paths
+        const filteredPrev = prev.filter((change) => !latest.has(change.path));
+        // Add the new changes at the beginning (most recent)
+        const updated = [...Array.from(latest.values()).reverse(), ...filteredPrev];
         // Keep only latest 500 changes
         return updated.slice(0, 500);
       });
@@ -2,6 +2,7 @@ import { createContext, useContext, useState, useEffect, useCallback, useRef, Re
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { SessionContext, OrgMember } from "@/types";
+import { errorMessage } from "@/lib/utils";
 
 interface SessionContextValue {
   context: SessionContext | null;
@@ -54,7 +55,7 @@ export function SessionContextProvider({ children }: { children: ReactNode }) {
         const ctx = await invoke<SessionContext>("get_session_context");
         setContext(ctx);
       } catch (err) {
-        setError(err as string);
+        setError(errorMessage(err));
       } finally {
         setIsLoading(false);
       }
@@ -108,7 +109,7 @@ export function SessionContextProvider({ children }: { children: ReactNode }) {
       await invoke("set_session_context", { context: ctx });
       setError(null);
     } catch (err) {
-      setError(err as string);
+      setError(errorMessage(err));
       throw err;
     }
   }, []);
@@ -118,7 +119,7 @@ export function SessionContextProvider({ children }: { children: ReactNode }) {
       await invoke("clear_session_context");
       setError(null);
     } catch (err) {
-      setError(err as string);
+      setError(errorMessage(err));
       throw err;
     }
   }, []);
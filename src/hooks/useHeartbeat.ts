@@ -14,6 +14,12 @@ interface HeartbeatStatus {
   status: HeartbeatResponse | null;
   is_loading: boolean;
   error: string | null;
+  failure_kind: 'offline' | 'degraded' | 'error' | null;
+  consecutive_failures: number;
+  paused: boolean;
+  successes_total: number;
+  failures_total: number;
+  last_success_at: number | null; // epoch millis
 }
 
 export function useHeartbeat(url: string) {
@@ -21,6 +27,12 @@ export function useHeartbeat(url: string) {
     status: null,
     is_loading: false,
     error: null,
+    failure_kind: null,
+    consecutive_failures: 0,
+    paused: false,
+    successes_total: 0,
+    failures_total: 0,
+    last_success_at: null,
   });
 
   useEffect(() => {
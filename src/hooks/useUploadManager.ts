@@ -2,6 +2,7 @@ import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { UploadConfig, UploadProgress } from '@/types';
+import { errorMessage } from '@/lib/utils';
 
 export function useUploadManager() {
   const [config, setConfig] = useState<UploadConfig | null>(null);
@@ -26,7 +27,7 @@ export function useUploadManager() {
         setQueueSize(currentQueueSize);
         setError(null);
       } catch (err) {
-        setError(err as string);
+        setError(errorMessage(err));
         console.error('Failed to load upload data:', err);
       } finally {
         setIsLoading(false);
@@ -69,7 +70,7 @@ export function useUploadManager() {
       setConfig(newConfig);
       setError(null);
     } catch (err) {
-      setError(err as string);
+      setError(errorMessage(err));
       throw err;
     }
   }, []);
@@ -84,7 +85,7 @@ export function useUploadManager() {
       }
       setError(null);
     } catch (err) {
-      setError(err as string);
+      setError(errorMessage(err));
       throw err;
     }
   }, [progress]);
@@ -95,7 +96,7 @@ export function useUploadManager() {
       await invoke('trigger_manual_upload', { filePath, basePath });
       setError(null);
     } catch (err) {
-      setError(err as string);
+      setError(errorMessage(err));
       throw err;
     }
   }, []);
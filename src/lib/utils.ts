@@ -1,6 +1,31 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { AppError } from "@/types"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+/** Readable message for an error rejected by `invoke`, structured or not. */
+export function errorMessage(err: unknown): string {
+  if (!err || typeof err !== "object" || !("kind" in err)) return String(err)
+  const e = err as AppError
+  switch (e.kind) {
+    case "folder_not_found":
+      return `Folder does not exist: ${e.path}`
+    case "not_a_directory":
+      return `Path is not a directory: ${e.path}`
+    case "folder_not_watched":
+      return `Folder is not being watched: ${e.path}`
+    case "too_many_folders":
+      return `Already watching the maximum of ${e.max} folders`
+    case "not_authenticated":
+      return "Not authenticated"
+    case "no_active_heartbeat":
+      return "No active heartbeat"
+    case "uploads_disabled":
+      return "Uploads are disabled"
+    default:
+      return e.message
+  }
+}
@@ -8,4 +8,8 @@ export interface DeviceInfo {
   os_type: string;
   device_id: string;
   device_fingerprint: string;
+  battery_percent: number | null;
+  on_ac_power: boolean | null;
+  disk_total_bytes: number | null;
+  disk_available_bytes: number | null;
 } 
\ No newline at end of file
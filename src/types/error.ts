@@ -0,0 +1,13 @@
+/** Error rejected by Tauri commands, tagged by `kind`. */
+export type AppError =
+  | { kind: 'folder_not_found'; path: string }
+  | { kind: 'not_a_directory'; path: string }
+  | { kind: 'folder_not_watched'; path: string }
+  | { kind: 'too_many_folders'; max: number }
+  | { kind: 'network'; status: number | null; message: string }
+  | { kind: 'not_authenticated' }
+  | { kind: 'config'; fields: string[]; message: string }
+  | { kind: 'no_active_heartbeat' }
+  | { kind: 'uploads_disabled' }
+  | { kind: 'not_found'; message: string }
+  | { kind: 'other'; message: string };
@@ -1,6 +1,18 @@
 export interface FileChangeEvent {
   path: string;
   event_type: string;
+  old_path?: string;
   timestamp: number;
   upload_status?: 'pending' | 'queued' | 'uploading' | 'uploaded' | 'failed' | 'ignored' | 'directory';
-} 
\ No newline at end of file
+} 
+export interface WatchErrorEvent {
+  path: string;
+  reason: string;
+}
+
+export interface InitialScanComplete {
+  folder: string;
+  total_files: number;
+  enqueued: number;
+  ignored: number;
+}
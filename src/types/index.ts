@@ -1,3 +1,4 @@
 export * from './device';
+export * from './error';
 export * from './file-system';
 export * from './upload'; 
\ No newline at end of file
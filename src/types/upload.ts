@@ -4,7 +4,77 @@ export interface UploadConfig {
   ignored_patterns: string[];
   upload_delay_ms: number;
   max_concurrent_uploads: number;
+  max_concurrent_metadata_updates: number;
+  max_in_flight_batches: number;
+  max_batch_size: number;
   ignore_existing_files: boolean;
+  max_upload_memory_bytes: number;
+  skip_unchanged_modifications: boolean;
+  delete_after_upload: boolean;
+  delete_after_upload_delay_secs: number;
+  delete_protected_patterns: string[];
+  startup_delay_ms: number;
+  detect_hard_links: boolean;
+  dedupe_hardlinks: boolean;
+  max_key_length: number | null;
+  key_truncation: 'skip' | 'hash';
+  verify_before_upload: boolean;
+  verify_after_upload: boolean;
+  completion_webhook_url: string | null;
+  failure_webhook_url: string | null;
+  initial_scan_always_verify: boolean;
+  max_retry_count: number;
+  retry_delay_secs: number;
+  upload_order: 'fifo' | 'smallest_first' | 'largest_first';
+  max_watched_folders: number;
+  respect_gitignore: boolean;
+  respect_syncignore: boolean;
+  max_debounce_ms: number | null;
+  max_watch_depth: number | null;
+  max_file_size_bytes: number | null;
+  upload_only_on_ac: boolean;
+  upload_windows: UploadWindow[]; // local time, empty = always
+  integrity_header: 'none' | 'goog_hash' | 'content_md5';
+  request_timeout_secs: number;
+  upload_timeout_secs: number;
+  dry_run: boolean;
+  include_patterns: string[];
+  dedupe_by_content: boolean;
+  multipart_threshold_bytes: number | null;
+  parallel_parts: number;
+  resumable_uploads: boolean;
+  compress_uploads: boolean;
+  encryption_key: string | null; // base64, 32 bytes
+  extra_headers: Record<string, string>;
+  content_type_overrides: Record<string, string>; // lowercase extension, no dot
+  heartbeat_extra_headers: Record<string, string>;
+  max_queue_size: number | null;
+  queue_full_policy: 'reject_new' | 'drop_oldest';
+  max_item_age_secs: number | null;
+  sync_deletes: boolean;
+  rename_coalesce_ms: number;
+  file_change_batch_ms: number;
+  emit_file_change_events: boolean;
+  audit_log: boolean;
+  follow_symlinks: boolean;
+}
+
+/** Per-folder settings passed to `start_watching`; unset fields use the global config. */
+export interface UploadConfigOverride {
+  ignore_existing_files?: boolean;
+  ignored_patterns?: string[];
+  include_patterns?: string[];
+  respect_gitignore?: boolean;
+  respect_syncignore?: boolean;
+  skip_unchanged_modifications?: boolean;
+  max_file_size_bytes?: number;
+  max_watch_depth?: number;
+  follow_symlinks?: boolean;
+}
+
+export interface UploadWindow {
+  start: string; // HH:MM
+  end: string;
 }
 
 export interface UploadProgress {
@@ -13,13 +83,25 @@ export interface UploadProgress {
   total_failed: number;
   in_flight: number;
   current_uploading: string | null;
+  waiting_for_power: boolean;
+  schedule_resumes_at: number | null;
+  bytes_uploaded_total: number;
+  current_bytes_per_sec: number;
+  estimated_seconds_remaining: number | null; // best-effort
+  average_upload_ms: number | null;
 }
 
 export interface UploadItem {
   path: string;
   relative_path: string;
+  base_path: string;
+  remote_key: string;
   timestamp: number;
+  first_seen: number;
   retry_count: number;
+  file_size: number;
+  not_before: number;
+  expiry_requeues: number;
 }
 
 export interface UploadEvent {
@@ -29,10 +111,16 @@ export interface UploadEvent {
 
 export interface FileUploadStatus {
   relative_path: string;
-  status: 'pending' | 'queued' | 'uploading' | 'uploaded' | 'failed' | 'ignored' | 'directory';
+  status: 'pending' | 'queued' | 'uploading' | 'uploaded' | 'failed' | 'ignored' | 'directory' | 'key_too_long' | 'cancelled' | 'dry_run' | 'queue_full' | 'expired' | 'deleted';
   error?: string;
 }
 
+export interface FileUploadBytes {
+  relative_path: string;
+  uploaded: number;
+  total: number;
+}
+
 export interface SessionContext {
   session_user_id: string | null;
   session_metadata: Record<string, string> | null;
@@ -45,4 +133,58 @@ export interface OrgMember {
   last_name: string | null;
   email: string;
   image_url: string | null;
-}
\ No newline at end of file
+}
+
+export interface FailedUploadInfo {
+  relative_path: string;
+  base_path: string;
+  error: string;
+}
+
+export interface RemoteKeyPreview {
+  relative_path: string;
+  remote_key: string | null;
+  truncated: boolean;
+}
+
+export interface ActivityEntry {
+  timestamp: number; // Unix timestamp in millis
+  level: 'error' | 'warn';
+  message: string;
+}
+
+export interface QueueFullEvent {
+  relative_path: string;
+  max_queue_size: number;
+  policy: 'reject_new' | 'drop_oldest';
+}
+
+export interface ConnectionTestResult {
+  reachable: boolean;
+  authenticated: boolean;
+  latency_ms: number | null;
+  error: string | null;
+}
+
+/** Second element of the `upload_failed` event payload, after the relative path. */
+export interface UploadError {
+  message: string;
+  category: 'network' | 'auth' | 'url_expired' | 'server' | 'client' | 'local';
+  status_code: number | null;
+  body: string | null; // truncated
+}
+
+export interface FileUploadedEvent {
+  relative_path: string;
+  duration_ms: number | null; // null when the server already had the file
+}
+
+export interface BatchStartedEvent {
+  file_count: number;
+}
+
+export interface BatchCompletedEvent {
+  exists: number;
+  needs_upload: number;
+  errors: number;
+}